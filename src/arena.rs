@@ -0,0 +1,123 @@
+// Generational arena. Stores things in slots that never move, so a Handle to one stays valid for as
+// long as it's alive. When something is removed its slot gets reused, but with a new generation, so
+// an old handle to it just finds nothing instead of finding whatever moved in. This is what lets
+// enemies point at each other (a summoner at its minions).
+
+// Stable reference to something in an Arena
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: usize,
+    generation: u32,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+pub struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>, // Empty slots ready to be reused
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle {
+        match self.free.pop() {
+            Some( index ) => {
+                let slot = &mut self.slots[index];
+                slot.generation += 1;
+                slot.value = Some(value);
+                Handle { index, generation: slot.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, value: Some(value) });
+                Handle { index: self.slots.len() - 1, generation: 0 }
+            }
+        }
+    }
+
+    // Takes the value out, None if the handle is stale
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let slot = self.slots.get_mut(handle.index)?;
+        if slot.generation != handle.generation || slot.value.is_none() {
+            return None;
+        }
+        self.free.push(handle.index);
+        slot.value.take()
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        match self.slots.get(handle.index) {
+            Some( slot ) if slot.generation == handle.generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        match self.slots.get_mut(handle.index) {
+            Some( slot ) if slot.generation == handle.generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    // Handles to everything in the arena, in slot order. Handy for looping while also looking
+    // things up by handle.
+    pub fn handles(&self) -> Vec<Handle> {
+        self.iter_with_handles().map(|(handle, _)| handle).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+
+    pub fn iter_with_handles(&self) -> impl Iterator<Item = (Handle, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.value.as_ref().map(|value| (Handle { index, generation: slot.generation }, value))
+        })
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Arena<T> {
+        Arena::new()
+    }
+}
+
+impl<T> From<Vec<T>> for Arena<T> {
+    fn from(values: Vec<T>) -> Arena<T> {
+        let mut arena = Arena::new();
+        for value in values {
+            arena.insert(value);
+        }
+        arena
+    }
+}
+
+// arena[handle] for when the handle is known to be alive, panics like a bad Vec index otherwise
+impl<T> std::ops::Index<Handle> for Arena<T> {
+    type Output = T;
+    fn index(&self, handle: Handle) -> &T {
+        self.get(handle).expect("stale arena handle")
+    }
+}
+
+impl<T> std::ops::IndexMut<Handle> for Arena<T> {
+    fn index_mut(&mut self, handle: Handle) -> &mut T {
+        self.get_mut(handle).expect("stale arena handle")
+    }
+}
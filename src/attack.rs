@@ -1,7 +1,31 @@
-
-use crate::boxes::Box;
+use crate::boxes::{Box, Layer};
 use crate::util::*;
 use crate::entity::*;
+use crate::enemydef::*;
+use std::time::{Duration, Instant};
+
+//How long the impact effect stays on screen after a projectile hits a rock or wall
+pub const ATK_IMPACT_TIME: u64 = 150;
+//Homing shots give up and fly straight after this long (ms) so they can't circle forever
+pub const HOMING_TIME: u128 = 2500;
+//Size a puddle spreads out to once it lands
+pub const PUDDLE_SIZE: u32 = 48;
+//How long (ms) a puddle shot flies before it lands, if it doesn't hit anything first
+pub const PUDDLE_TRAVEL_TIME: u128 = 700;
+//Speed of the pieces a splitting shot breaks into
+pub const SPLIT_PIECE_SPEED: f32 = 2.5;
+
+//What a projectile does besides flying in a straight line
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProjectileBehavior {
+    Straight,
+    Homing(f32), // Turns towards the player by up to this many radians a frame
+    Accelerating(f32, f32), // Speeds up by the first value every frame, up to the second
+    Sine(f32, f32), // Weaves side to side, amplitude in pixels and wavelength in frames
+    Bouncing(u32), // Bounces off rocks and walls this many times
+    Splitting(u32), // Breaks into this many pieces when it hits something
+    Puddle(u128), // Lands and leaves a hazard behind that lasts this long (ms)
+}
 
 #[derive(Clone)]
 pub struct AtkProjectile {
@@ -10,7 +34,12 @@ pub struct AtkProjectile {
 	pub speed: f32,
     pub movement_vec: Vec2<f32>,
     pub damage: i32,
-    pub kind: EnemyKind,
+    pub sprite: &'static str, //Texture path, draw looks it up
+    pub behavior: ProjectileBehavior,
+    pub spawned: Instant,
+    pub frames: f32, //Frames it's been alive, scaled by time_scale. Drives the sine weave.
+    pub base_pos: Vec2<f32>, //Where it would be without the weave
+    pub landed: bool, //Puddles stop once they land
 }
 
 impl AtkProjectile {
@@ -21,78 +50,196 @@ impl AtkProjectile {
         	speed : get_speed(enemy_kind), //How fast it is
         	damage : get_damage(enemy_kind), //How much it will damage the player when collided with
         	box_es : get_boxes(enemy_kind), //The hitbox of it
-            kind : *enemy_kind, //What kind of attack it is
+            sprite : enemy_def(*enemy_kind).projectile_sprite.as_str(), //What it looks like
+            behavior : enemy_def(*enemy_kind).projectile_behavior, //How it moves
+            spawned : Instant::now(),
+            frames : 0.0,
+            base_pos : pos,
+            landed : false,
+        }
+    }
+
+    //Same shot with a different behaviour, for emitters and patterns that want something special
+    pub fn with_behavior(mut self, behavior: ProjectileBehavior) -> AtkProjectile {
+        self.behavior = behavior;
+        self
+    }
+
+    //Moves the shot one frame. target is where homing shots steer to.
+    pub fn step(&mut self, target: Vec2<f32>, time_scale: f32) {
+        if self.landed {
+            return;
         }
+        self.frames += time_scale;
+
+        match self.behavior {
+            ProjectileBehavior::Homing(turn) => {
+                if self.spawned.elapsed().as_millis() < HOMING_TIME {
+                    let want = (target.y - self.pos.y).atan2(target.x - self.pos.x);
+                    let have = self.movement_vec.y.atan2(self.movement_vec.x);
+                    //Shortest way round, in -PI..PI
+                    let mut diff = want - have;
+                    while diff > std::f32::consts::PI { diff -= std::f32::consts::PI * 2.0; }
+                    while diff < -std::f32::consts::PI { diff += std::f32::consts::PI * 2.0; }
+                    let angle = have + diff.clamp(-turn * time_scale, turn * time_scale);
+                    self.movement_vec = Vec2::new(angle.cos(), angle.sin());
+                }
+            }
+            ProjectileBehavior::Accelerating(gain, max) => {
+                self.speed = (self.speed + gain * time_scale).min(max);
+            }
+            _ => {}
+        }
+
+        self.base_pos.x += self.movement_vec.x * self.speed * time_scale;
+        self.base_pos.y += self.movement_vec.y * self.speed * time_scale;
+
+        match self.behavior {
+            ProjectileBehavior::Sine(amplitude, wavelength) => {
+                //Offset sideways from the straight line it's really travelling along
+                let side = (self.frames / wavelength * std::f32::consts::PI * 2.0).sin() * amplitude;
+                self.pos.x = self.base_pos.x - self.movement_vec.y * side;
+                self.pos.y = self.base_pos.y + self.movement_vec.x * side;
+            }
+            ProjectileBehavior::Puddle(_) => {
+                self.pos = self.base_pos;
+                if self.spawned.elapsed().as_millis() >= PUDDLE_TRAVEL_TIME {
+                    self.land();
+                }
+            }
+            _ => {
+                self.pos = self.base_pos;
+            }
+        }
+    }
+
+    //Sent back the way it came by the player's swing. Now it hurts enemies instead of the player.
+    pub fn reflect(&mut self) {
+        self.movement_vec = Vec2::new(-self.movement_vec.x, -self.movement_vec.y);
+        self.box_es = self.box_es.with_layer(Layer::PlayerProjectile);
+        //Homing shots would turn straight back round at the player
+        if matches!(self.behavior, ProjectileBehavior::Homing(_)) {
+            self.behavior = ProjectileBehavior::Straight;
+        }
+    }
+
+    //True if the player could still send it back, landed puddles stay put
+    pub fn reflectable(&self) -> bool {
+        self.box_es.layer == Layer::EnemyProjectile && !self.landed
+    }
+
+    //Puddle shots stop and spread out
+    pub fn land(&mut self) {
+        self.landed = true;
+        self.spawned = Instant::now();
+        //Only the size changes, it still hurts whoever it hurt before
+        self.box_es.hitbox = Vec2::new(PUDDLE_SIZE, PUDDLE_SIZE);
+        self.box_es.walkbox = Vec2::new(PUDDLE_SIZE, PUDDLE_SIZE);
+    }
+
+    //True once a puddle has dried up
+    pub fn is_finished(&self) -> bool {
+        match self.behavior {
+            ProjectileBehavior::Puddle(linger) => self.landed && self.spawned.elapsed().as_millis() >= linger,
+            _ => false,
+        }
+    }
+
+    //Pieces a splitting shot breaks into when it hits something at pos, thrown back the way it came
+    pub fn split_pieces(&self, count: u32, pos: Vec2<f32>) -> Vec<AtkProjectile> {
+        let back = (-self.movement_vec.y).atan2(-self.movement_vec.x);
+        let mut pieces = Vec::new();
+        for i in 0..count {
+            //Fan out over half a circle centred on the way back
+            let angle = back - std::f32::consts::FRAC_PI_2 + std::f32::consts::PI * (i as f32 + 0.5) / count as f32;
+            let mut piece = self.clone();
+            piece.pos = pos;
+            piece.base_pos = pos;
+            piece.movement_vec = Vec2::new(angle.cos(), angle.sin());
+            piece.speed = SPLIT_PIECE_SPEED;
+            piece.behavior = ProjectileBehavior::Straight;
+            piece.box_es.hitbox = Vec2::new((self.box_es.hitbox.x / 2).max(6), (self.box_es.hitbox.y / 2).max(6));
+            piece.box_es.walkbox = Vec2::new((self.box_es.walkbox.x / 2).max(6), (self.box_es.walkbox.y / 2).max(6));
+            pieces.push(piece);
+        }
+        pieces
+    }
+}
+
+//Describes a volley: how many shots, how wide they fan out, how much each volley turns and how
+//long to wait between volleys. Any enemy can fire one with fire_emitter in behavior.rs.
+#[derive(Copy, Clone, Debug)]
+pub struct Emitter {
+    pub count: u32,
+    pub spread: f32, //Total angle the shots cover in radians, a full circle spaces them evenly
+    pub rotation: f32, //Extra turn added every volley, in radians
+    pub delay: u128, //Time between volleys in ms
+    pub behavior: Option<ProjectileBehavior>, //None uses the kind's own projectile behaviour
+}
+
+impl Emitter {
+    //Directions for volley number volley, centred on aim
+    pub fn directions(&self, aim: Vec2<f32>, volley: u32) -> Vec<Vec2<f32>> {
+        let centre = aim.y.atan2(aim.x) + self.rotation * volley as f32;
+        let full_circle = self.spread >= std::f32::consts::PI * 2.0 - 0.001;
+        let mut dirs = Vec::new();
+        for i in 0..self.count {
+            let angle = if self.count == 1 {
+                centre
+            } else if full_circle {
+                centre + self.spread * i as f32 / self.count as f32
+            } else {
+                centre - self.spread / 2.0 + self.spread * i as f32 / (self.count - 1) as f32
+            };
+            dirs.push(Vec2::new(angle.cos(), angle.sin()));
+        }
+        dirs
+    }
+
+    //Whether enough time has passed since last for another volley
+    pub fn ready(&self, last: Option<Instant>) -> bool {
+        match last {
+            Some( time ) => time.elapsed().as_millis() >= self.delay,
+            None => true,
+        }
+    }
+}
+
+//Left behind when a projectile hits something that blocks it, only used for drawing
+#[derive(Clone)]
+pub struct AtkImpact {
+    pub pos: Vec2<f32>,
+    pub box_es: Box,
+    pub time: Instant,
+}
+
+impl AtkImpact {
+    pub fn new(atk : &AtkProjectile) -> AtkImpact {
+        AtkImpact {
+            pos : atk.pos,
+            box_es : atk.box_es,
+            time : Instant::now(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time.elapsed() >= Duration::from_millis(ATK_IMPACT_TIME)
     }
 }
- //Set speed of attack projectile based on what kind of enemy it is
+ //Speed, damage and hitbox of the projectile come from the kind of enemy that fired it
 fn get_speed(enemy_kind : & EnemyKind) -> f32 {
-	let speed;
-    match enemy_kind {
-		EnemyKind::Health => {
-			speed = HEALTH_ENEMY_ATK_PROJSPEED;
-		}
-		EnemyKind::Speed =>{
-			speed = SPEED_ENEMY_ATK_PROJSPEED;
-		}
-		EnemyKind::Attack => {
-			speed = ATTACK_ENEMY_ATK_PROJSPEED;
-		}
-		_ => {speed = 0.0;}
-	}
-	return speed;
+	enemy_def(*enemy_kind).projectile_speed
 }
 
- //Set damage of attack projectile based on what kind of enemy it is
 fn get_damage(enemy_kind : & EnemyKind) -> i32 {
-	let damage;
-    match enemy_kind {
-		EnemyKind::Health => {
-			damage = HEALTH_ENEMY_ATK_DMG;
-		}
-		EnemyKind::Speed =>{
-			damage = SPEED_ENEMY_ATK_DMG;
-		}
-		EnemyKind::Attack => {
-			damage = ATTACK_ENEMY_ATK_DMG;
-		}
-		_ => {damage = 0;}
-	}
-	return damage;
+	enemy_def(*enemy_kind).projectile_damage
 }
 
- //Set hitbox and walkbox of attack projectile based on what kind of enemy it is
 fn get_boxes(enemy_kind : & EnemyKind) -> Box {
-	let box_es;
-    match enemy_kind {
-		EnemyKind::Health => {
-			box_es = Box::new(
-                Vec2::new(HEALTH_ENEMY_ATK_WIDTH, HEALTH_ENEMY_ATK_HEIGHT), //hitbox (deals damage)
-                Vec2::new(HEALTH_ENEMY_ATK_WIDTH, HEALTH_ENEMY_ATK_HEIGHT), //walkbox (interacts with enviornment)
-                Vec2::new(0, 0) //NOT FOR USE WITH ANYTHING OTHER THAN PLAYER
-            );
-		}
-		EnemyKind::Speed =>{
-			box_es = Box::new(
-                Vec2::new(SPEED_ENEMY_ATK_WIDTH, SPEED_ENEMY_ATK_HEIGHT),
-                Vec2::new(SPEED_ENEMY_ATK_WIDTH, SPEED_ENEMY_ATK_HEIGHT),
-                Vec2::new(0, 0)
-            );
-		}
-		EnemyKind::Attack => {
-			box_es = Box::new(
-                Vec2::new(ATTACK_ENEMY_ATK_WIDTH, ATTACK_ENEMY_ATK_HEIGHT),
-                Vec2::new(ATTACK_ENEMY_ATK_WIDTH, ATTACK_ENEMY_ATK_HEIGHT),
-                Vec2::new(0, 0)
-            );
-		}
-		_ => {
-			box_es = Box::new(
-                Vec2::new(0, 0), //hitbox (deals damage)
-                Vec2::new(0, 0), //walkbox (interacts with enviornment)
-                Vec2::new(0, 0) //NOT FOR USE WITH ANYTHING OTHER THAN PLAYER
-            );
-		}
-	}
-	return box_es;
+	let size = enemy_def(*enemy_kind).projectile_size;
+	Box::new(
+		size, //hitbox (deals damage)
+		size, //walkbox (interacts with enviornment)
+		Vec2::new(0, 0) //NOT FOR USE WITH ANYTHING OTHER THAN PLAYER
+	).with_layer(Layer::EnemyProjectile)
 }
@@ -0,0 +1,422 @@
+use crate::blackboard::*;
+use crate::util::*;
+use crate::entity::*;
+use crate::attack::*;
+use crate::perception::*;
+use crate::navigation::dir_from_vec;
+use std::time::Instant;
+
+// Shared enemy state machine. Each enemy kind is a small struct that implements EnemyBehavior,
+// overriding only the states or knobs where it differs. The building blocks below are what the
+// default states are made of so new kinds can mix them together too.
+
+pub trait EnemyBehavior {
+
+    fn update(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if self.perceives() {
+            perceive(enemy, blackboard, self.sight_range());
+            // Lost track of the player, go look where they were last
+            if enemy.state == State::Chase && !aware(enemy) {
+                start_search(enemy);
+            }
+        }
+        if self.reaims_on_dodge() {
+            reaim_on_dodge(enemy, blackboard);
+        }
+
+        match enemy.state {
+            State::Attack => {
+                self.attack(enemy, blackboard);
+            }
+            State::Retreat => {
+                self.retreat(enemy, blackboard);
+            }
+            State::TakeCover => {
+                self.take_cover(enemy, blackboard);
+            }
+            State::Chase => {
+                self.chase(enemy, blackboard);
+            }
+            State::Heal => {
+                self.heal(enemy, blackboard);
+            }
+            State::Idle => {
+                self.idle(enemy, blackboard);
+            }
+            State::Search => {
+                self.search(enemy, blackboard);
+            }
+        }
+    }
+
+    // Knobs the default states check
+
+    // Has to notice the player before it knows where they are, see perception.rs
+    fn perceives(&self) -> bool { true }
+    // How far (px) it can see
+    fn sight_range(&self) -> f64 { 300.0 }
+
+    // Only dodge charges and retreat while the player is above half health
+    fn cautious(&self) -> bool { false }
+    // Turns to follow the player's dodge rolls instead of swinging at where they were
+    fn reaims_on_dodge(&self) -> bool { false }
+    // Goes to a red enemy for healing when low
+    fn seeks_healing(&self) -> bool { true }
+    // Extra reason to drop out of Chase back to Idle
+    fn gives_up_chase(&self, _enemy: & mut Enemy, _blackboard: &BlackBoard) -> bool { false }
+    // How the enemy runs away when it has nowhere better to go
+    fn flee(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        flee_by_dir(enemy, blackboard);
+    }
+
+    // States
+
+    fn attack(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if dodge_charge(enemy, blackboard, self.cautious()) {
+            return;
+        }
+
+        attack_player(enemy, blackboard);
+
+        // if player is far, chase them
+        if !Enemy::player_close(enemy, blackboard){
+            enemy.state = State::Chase;
+        }
+
+        low_hp_transitions(enemy, blackboard, self.cautious(), self.seeks_healing());
+    }
+
+    fn retreat(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        self.flee(enemy, blackboard);
+
+        // if last enemy in room, switch to chase
+        if blackboard.enemy_quantity == 1 && !(self.cautious() && blackboard.player_charged)
+        {
+            enemy.state = State::Chase;
+        }
+    }
+
+    fn take_cover(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        // Hide behind the nearest rock, if there isn't one just back away from the player
+        if !enemy.seek_cover(blackboard) {
+            self.flee(enemy, blackboard);
+        }
+        leave_cover_after_charge(enemy, blackboard);
+    }
+
+    fn chase(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if dodge_charge(enemy, blackboard, self.cautious()) {
+            return;
+        }
+
+        chase_player(enemy, blackboard);
+
+        if self.gives_up_chase(enemy, blackboard) {
+            enemy.state = State::Idle;
+        }
+
+        // if close to player, switch to attack
+        if Enemy::player_close(enemy, blackboard)
+        {
+            enemy.state = State::Attack;
+        }
+
+        low_hp_transitions(enemy, blackboard, self.cautious(), self.seeks_healing());
+    }
+
+    fn heal(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        heal_at_red(enemy, blackboard);
+    }
+
+    fn idle(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        wait_for_player(enemy, blackboard);
+    }
+
+    fn search(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if aware(enemy) {
+            set_patrol_wait(enemy, None);
+            enemy.state = State::Chase;
+            return;
+        }
+        if search(enemy, blackboard) || enemy.state_timer.elapsed().as_millis() >= SEARCH_GIVE_UP_TIME {
+            stop_searching(enemy);
+            enemy.state = State::Idle;
+        }
+    }
+}
+
+// Building blocks
+
+// Goes to TakeCover if the player is charging up close. Returns true if it did.
+pub fn dodge_charge(enemy: & mut Enemy, blackboard: &BlackBoard, cautious: bool) -> bool {
+    // Cautious enemies stand their ground when the player is already hurt
+    if blackboard.player_charged && Enemy::distance_to_player(enemy, blackboard) < 100.0 &&
+    (!cautious || player_healthy(blackboard)) {
+        enemy.state = State::TakeCover;
+        return true;
+    }
+    false
+}
+
+pub fn player_healthy(blackboard: &BlackBoard) -> bool {
+    blackboard.player_health as f32 > (blackboard.player_max_health as f32 * 0.5)
+}
+
+pub fn low_on_life(enemy: &Enemy) -> bool {
+    (enemy.hp as f32) <= enemy.m_hp as f32/3.0
+}
+
+pub fn health_enemy_in_room(blackboard: &BlackBoard) -> bool {
+    blackboard.types_in_room.contains(&EnemyKind::Health)
+}
+
+// Faces where the player's roll is going to end. A swing already going the wrong way is started
+// over towards them.
+pub fn reaim_on_dodge(enemy: & mut Enemy, blackboard: &BlackBoard){
+    let target = match blackboard.player_dodge {
+        Some( target ) => target,
+        None => return,
+    };
+    if !aware(enemy) {
+        return;
+    }
+    let dir = dir_from_vec(Vec2::new(target.x - enemy.pos.x, target.y - enemy.pos.y), enemy.dir);
+    if dir == enemy.dir {
+        return;
+    }
+    enemy.dir = dir;
+    if enemy.recently_attacked() {
+        enemy.last_attack_time = Some(Instant::now());
+    }
+}
+
+// Shoots if ranged, swings if not
+pub fn attack_player(enemy: & mut Enemy, blackboard: &BlackBoard){
+    if enemy.is_ranged{
+        enemy.signal_shot();
+        // Don't waste shots on a rock, player_close sends us to find a clear shot
+        if enemy.is_shooting && Enemy::player_in_sight(enemy, blackboard){
+            // Lead the shot to where a rolling player is going to be
+            let target = match blackboard.player_dodge {
+                Some( target ) => target,
+                None => blackboard.playerpos,
+            };
+            shoot_at(enemy, target);
+            enemy.is_shooting = false;
+        }
+    } else {
+        enemy.signal_attack();
+    }
+}
+
+// Fires one projectile from the enemy towards a point
+pub fn shoot_at(enemy: & mut Enemy, target: Vec2<f32>){
+    let mut vector = Vec2::new(target.x - enemy.pos.x, target.y - enemy.pos.y);
+    let length = ((vector.x * vector.x + vector.y * vector.y) as f64).sqrt();
+    if length == 0.0 {
+        return;
+    }
+
+    // normalize vector
+    vector.x /= length as f32;
+    vector.y /= length as f32;
+    shoot_dir(enemy, vector);
+}
+
+// Fires one projectile along a normalized direction
+pub fn shoot_dir(enemy: & mut Enemy, dir: Vec2<f32>){
+    let new_atk = AtkProjectile::new(enemy.pos, dir, &enemy.kind);
+    enemy.atk_list.push(new_atk);
+}
+
+// Fires one volley of an emitter centred on aim. volley counts up from 0 and turns the pattern by
+// the emitter's rotation each time.
+pub fn fire_emitter(enemy: & mut Enemy, emitter: &Emitter, aim: Vec2<f32>, volley: u32){
+    let kind = enemy.kind;
+    fire_emitter_as(enemy, emitter, aim, volley, kind);
+}
+
+// Same as fire_emitter but the shots are the ones kind fires, the boss borrows other kinds' shots
+pub fn fire_emitter_as(enemy: & mut Enemy, emitter: &Emitter, aim: Vec2<f32>, volley: u32, kind: EnemyKind){
+    for dir in emitter.directions(aim, volley) {
+        let mut new_atk = AtkProjectile::new(enemy.pos, dir, &kind);
+        match emitter.behavior {
+            Some( behavior ) => new_atk = new_atk.with_behavior(behavior),
+            None => {}
+        }
+        enemy.atk_list.push(new_atk);
+    }
+}
+
+// Unit vector from the enemy to the player, None if they're on top of each other
+pub fn aim_at_player(enemy: &Enemy, blackboard: &BlackBoard) -> Option<Vec2<f32>> {
+    let vector = Vec2::new(blackboard.playerpos.x - enemy.pos.x, blackboard.playerpos.y - enemy.pos.y);
+    let length = (vector.x * vector.x + vector.y * vector.y).sqrt();
+    if length < 1.0 {
+        return None;
+    }
+    Some(Vec2::new(vector.x / length, vector.y / length))
+}
+
+// Retreat when low and there's no red enemy, or go heal if there is one
+pub fn low_hp_transitions(enemy: & mut Enemy, blackboard: &BlackBoard, cautious: bool, seeks_healing: bool){
+    if !low_on_life(enemy) || blackboard.enemy_quantity <= 1 {
+        return;
+    }
+
+    if !health_enemy_in_room(blackboard) && !blackboard.boss_fight && (!cautious || player_healthy(blackboard)) {
+        enemy.state = State::Retreat;
+    }
+
+    if seeks_healing && health_enemy_in_room(blackboard) {
+        enemy.state = State::Heal;
+    }
+}
+
+// Pathfinds one step towards the player
+pub fn chase_player(enemy: & mut Enemy, blackboard: &BlackBoard){
+    enemy.pathfinding(blackboard.playerpos, blackboard);
+    move_along(enemy);
+}
+
+// Moves the enemy along its movement_vec for this frame
+pub fn move_along(enemy: & mut Enemy){
+    enemy.pos.x += enemy.movement_vec.x * enemy.speed * enemy.time_scale;
+    enemy.pos.y += enemy.movement_vec.y * enemy.speed * enemy.time_scale;
+}
+
+// Backs away from the player based on which way the enemy is facing
+pub fn flee_by_dir(enemy: & mut Enemy, blackboard: &BlackBoard){
+    match enemy.dir {
+        Direction::Up => {
+            enemy.movement_vec.y = 1.0;
+        }
+        Direction::Down => {
+            enemy.movement_vec.y = -1.0;
+        }
+        Direction::Right => {
+            if enemy.pos.y < blackboard.playerpos.y - 5.0 {
+                enemy.movement_vec.x = -DIAGONAL_VEC;
+                enemy.movement_vec.y = -DIAGONAL_VEC;
+            }
+            else if enemy.pos.y > blackboard.playerpos.y + 5.0 {
+                enemy.movement_vec.x = -DIAGONAL_VEC;
+                enemy.movement_vec.y = DIAGONAL_VEC;
+            }
+            else {
+                enemy.movement_vec.x = -1.0;
+                enemy.movement_vec.y = 0.0;
+            }
+        }
+        Direction::Left => {
+            if enemy.pos.y < blackboard.playerpos.y - 5.0{
+                enemy.movement_vec.x = DIAGONAL_VEC;
+                enemy.movement_vec.y = -DIAGONAL_VEC;
+            }
+            else if enemy.pos.y > blackboard.playerpos.y + 5.0{
+                enemy.movement_vec.x = DIAGONAL_VEC;
+                enemy.movement_vec.y = DIAGONAL_VEC;
+            }
+            else{
+                enemy.movement_vec.x = 1.0;
+                enemy.movement_vec.y = 0.0;
+            }
+        }
+    }
+
+    move_along(enemy);
+}
+
+// Moves straight away from the player
+pub fn flee_straight(enemy: & mut Enemy, blackboard: &BlackBoard){
+    let vector = Vec2::new(enemy.pos.x - blackboard.playerpos.x, enemy.pos.y - blackboard.playerpos.y);
+    let length = (vector.x * vector.x + vector.y * vector.y).sqrt();
+    if length > 0.0 {
+        enemy.movement_vec.x = vector.x / length;
+        enemy.movement_vec.y = vector.y / length;
+    }
+    move_along(enemy);
+}
+
+// Charge is over, peek back out and attack
+pub fn leave_cover_after_charge(enemy: & mut Enemy, blackboard: &BlackBoard){
+    if !blackboard.player_charged {
+        enemy.cover_tile = None;
+        if enemy.is_ranged {
+            enemy.state = State::Attack;
+        } else {
+            enemy.state = State::Chase;
+        }
+    }
+}
+
+// Walks to the first red enemy and heals while touching it, back to Chase once healed
+pub fn heal_at_red(enemy: & mut Enemy, blackboard: &BlackBoard){
+
+    // if close to health enemy, start to heal
+    if !blackboard.health_enemy_hitbox.is_empty() && enemy.box_es.get_hitbox(enemy.pos).has_intersection(blackboard.health_enemy_hitbox[0])
+        && (enemy.hp as f32) < enemy.m_hp as f32 * 0.75 {
+        enemy.is_healing = true;
+        enemy.take_damage(-1, HEAL_TIME);
+    }
+    else{
+        enemy.is_healing = false; // if not close to heal enemy, change bool
+    }
+
+    // when not close to a heal enemy, but need to heal, find one and move to them
+    if !enemy.is_healing && health_enemy_in_room(blackboard) && !blackboard.health_enemy_pos.is_empty(){
+        enemy.pathfinding(blackboard.health_enemy_pos[0], blackboard);
+        move_along(enemy);
+    }
+
+    // if no health enemies left, move to chase
+    if !health_enemy_in_room(blackboard){
+        enemy.is_healing = false;
+        enemy.state = State::Chase;
+    }
+
+    // if healed move to chase
+    if (enemy.hp as f32) >= enemy.m_hp as f32 * 0.75 {
+        enemy.is_healing = false;
+        enemy.state = State::Chase;
+    }
+}
+
+// Patrols until it notices the player, or goes to check out something it half saw or heard
+pub fn wait_for_player(enemy: & mut Enemy, blackboard: &BlackBoard){
+    patrol(enemy, blackboard);
+
+    // Half saw or heard something
+    let curious = match enemy.perception {
+        Some( ref perception ) => perception.alert >= SEARCH_ALERT && perception.last_known_pos.is_some(),
+        None => false,
+    };
+    if aware(enemy) || blackboard.yellow_chicken {
+        notice(enemy);
+        set_patrol_wait(enemy, None);
+        enemy.state = State::Chase;
+    } else if curious {
+        start_search(enemy);
+    }
+}
+
+pub fn start_search(enemy: & mut Enemy){
+    set_patrol_wait(enemy, None);
+    enemy.state_timer = Instant::now();
+    enemy.state = State::Search;
+}
+
+// The one place an EnemyKind picks its behaviour
+pub fn behavior_kind(kind: EnemyKind) -> &'static dyn EnemyBehavior {
+    match kind {
+        EnemyKind::Attack => &crate::yellowenemy::YellowBehavior,
+        EnemyKind::Health => &crate::redenemy::RedBehavior,
+        EnemyKind::Speed => &crate::blueenemy::BlueBehavior,
+        EnemyKind::Flying => &crate::purpleenemy::PurpleBehavior,
+        EnemyKind::Charger => &crate::chargerenemy::ChargerBehavior,
+        EnemyKind::Turret => &crate::turretenemy::TurretBehavior,
+        EnemyKind::Summoner => &crate::summonerenemy::SummonerBehavior,
+        EnemyKind::Splitter | EnemyKind::Splitling => &crate::splitterenemy::SplitterBehavior,
+        EnemyKind::Final => &crate::finalenemy::FinalBehavior,
+    }
+}
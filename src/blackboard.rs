@@ -5,9 +5,15 @@ use crate::boxes::*;
 use crate::tile::*;
 use crate::room::*;
 use crate::player::*;
+use crate::navigation::*;
+use crate::perception::*;
 use sdl2::rect::Rect;
 
 use rand::Rng;
+use std::collections::VecDeque;
+
+// How many tiles away from itself an enemy will look for cover
+pub const COVER_SEARCH_RADIUS: i32 = 6;
 
 
 pub struct BlackBoard
@@ -20,12 +26,16 @@ pub struct BlackBoard
     pub player_speed: f32,
     pub player_attack: i32,
     pub player_charged: bool,
+    // Where the player's roll will end, None if they aren't rolling
+    pub player_dodge: Option<Vec2<f32>>,
     pub enemy_quantity: i32,
     pub health_enemy_pos: Vec<Vec2<f32>>,
     pub health_enemy_tile: Vec<Vec2<i32>>,
     pub health_enemy_hitbox: Vec<Rect>,
     pub types_in_room: Vec<EnemyKind>,
     pub yellow_chicken: bool,
+    // Something the player did this frame that enemies can hear: where and how far it carries
+    pub noise: Option<(Vec2<f32>, f64)>,
 
     pub boss_kind: EnemyKind,
 
@@ -33,7 +43,14 @@ pub struct BlackBoard
 
 
     pub cr_tiles: Vec<Vec<std::boxed::Box<dyn Tile>>>,
-
+    // Tiles with a crate on them, these break so they're updated every frame
+    pub crate_tiles: Vec<Vec2<i32>>,
+
+    // Shared paths to the player, rebuilt every frame. Flyers get their own since they can cut
+    // across pits and rocks.
+    pub nav_settings: NavSettings,
+    pub flow_field: FlowField,
+    pub fly_flow_field: FlowField,
 }
 
 impl BlackBoard{
@@ -47,6 +64,7 @@ impl BlackBoard{
             player_speed: -1.0,
             player_attack: -1,
             player_charged: false,
+            player_dodge: None,
             enemy_quantity: -1,
             //offset: 0,
             health_enemy_pos:Vec::new(),
@@ -54,12 +72,18 @@ impl BlackBoard{
             health_enemy_hitbox:Vec::<Rect>::new(),
             types_in_room: Vec::<EnemyKind>::new(),
             yellow_chicken: false,
+            noise: None,
 
             boss_kind: EnemyKind::Final,
 
             boss_fight: false,
             //Not updated normally, updated only when the room changes
             cr_tiles : Vec::new(),
+            crate_tiles: Vec::new(),
+
+            nav_settings: NavSettings::new(true, false),
+            flow_field: FlowField::new(NavSettings::new(true, false)),
+            fly_flow_field: FlowField::new(NavSettings::new(true, false)),
         }
     }
 
@@ -71,14 +95,40 @@ impl BlackBoard{
         self.player_max_health = game.player.m_hp;
         self.player_speed = game.player.speed;
         self.player_attack = game.player.attack;
-        self.player_charged = game.player.is_charging;
+        // Enemies start dodging as soon as the player starts holding a charge
+        self.player_charged = game.player.is_charging || game.player.holding_charge();
+        self.player_dodge = if game.player.dodging() { Some(game.player.dodge_end()) } else { None };
         self.enemy_quantity = BlackBoard::get_enemy_quantity(game);
         self.health_enemy_pos = BlackBoard::get_health_enemy_pos(game);
         self.health_enemy_tile = BlackBoard::get_health_enemy_tile(&self.health_enemy_pos);
         self.health_enemy_hitbox = BlackBoard::get_health_enemy_hitbox(game);
         self.types_in_room = BlackBoard::get_types_in_room(game);
         self.yellow_chicken = BlackBoard::active_yellow_retreat(game);
+        self.noise = BlackBoard::get_noise(game);
         self.boss_fight = self.check_boss_fight();
+        self.crate_tiles = game.current_room().props.solid_tiles();
+        self.update_flow_field();
+    }
+
+    // Every enemy chasing the player reads from this instead of running its own search
+    pub fn update_flow_field(&mut self){
+        if self.cr_tiles.is_empty() {
+            return;
+        }
+        let mut field = FlowField::new(self.nav_settings);
+        field.build(self.player_frame_tile, &|tile| self.is_passable(tile, MovementLayer::Walker));
+        self.flow_field = field;
+
+        let mut fly_field = FlowField::new(self.nav_settings);
+        fly_field.build(self.player_frame_tile, &|tile| self.is_passable(tile, MovementLayer::Flyer));
+        self.fly_flow_field = fly_field;
+    }
+
+    pub fn flow_field_for(&self, layer: MovementLayer) -> &FlowField {
+        match layer {
+            MovementLayer::Flyer => &self.fly_flow_field,
+            _ => &self.flow_field,
+        }
     }
 
     pub fn update_room(&mut self, game: &Game){
@@ -104,6 +154,17 @@ impl BlackBoard{
         //tiles.copy_from_slice(&game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].tiles[0..]);
     }
 
+    // Bombs are loud, swings less so
+    pub fn get_noise(game: &Game) -> Option<(Vec2<f32>, f64)> {
+        if game.player.using_bomb {
+            Some((game.player.pos_static, BOMB_NOISE_RADIUS))
+        } else if game.player.is_attacking {
+            Some((game.player.pos, ATTACK_NOISE_RADIUS))
+        } else {
+            None
+        }
+    }
+
     pub fn get_types_in_room(game: &Game) -> Vec<EnemyKind> {
         let mut v = vec![];
         for enemy in game.current_room().enemies.iter() {
@@ -168,7 +229,8 @@ impl BlackBoard{
 
         for enemy in game.current_room().enemies.iter()
         {
-            if !enemy.death {
+            // A splitter that just died still has its pieces to add, so the room isn't clear yet
+            if !enemy.death || enemy.has_pending_spawns() {
                 qty += 1;
             }
         }
@@ -176,12 +238,92 @@ impl BlackBoard{
         return qty;
     }
 
-    pub fn is_walkable(&self, tile: Vec2<i32>)-> bool {
+    // Whether an enemy on the given layer can move onto a tile. Crates only stop walkers.
+    pub fn is_passable(&self, tile: Vec2<i32>, layer: MovementLayer) -> bool {
+        if layer == MovementLayer::Walker && self.crate_tiles.contains(&tile) {
+            return false;
+        }
+        !layer.blocked_by(&self.cr_tiles[tile.y as usize][tile.x as usize].walkability())
+    }
+
+    // Tiles that stop projectiles also stop sight. Pits can be seen (and shot) across.
+    pub fn blocks_sight(&self, tile: Vec2<i32>) -> bool {
+        if tile.x < 0 || tile.x >= ROOM_WIDTH || tile.y < 0 || tile.y >= ROOM_HEIGHT {
+            return true;
+        }
+        if self.crate_tiles.contains(&tile) {
+            return true;
+        }
         match self.cr_tiles[tile.y as usize][tile.x as usize].walkability(){
-            Walkability::Wall | Walkability::Rock | Walkability::Pit => {false}
-            _ => {true}
+            Walkability::Wall | Walkability::Rock => {true}
+            _ => {false}
+        }
+    }
 
+    pub fn has_line_of_sight(&self, from: Vec2<f32>, to: Vec2<f32>) -> bool {
+        // Room tiles haven't been loaded yet, nothing to block with
+        if self.cr_tiles.is_empty() {
+            return true;
         }
+        line_clear(from, to, &|tile| self.blocks_sight(tile))
+    }
+
+    pub fn player_in_sight(&self, pos: Vec2<f32>) -> bool {
+        self.has_line_of_sight(pos, self.playerpos)
+    }
+
+    // Pixel position of the middle of a tile
+    pub fn tile_center(tile: Vec2<i32>) -> Vec2<f32> {
+        Vec2::new(
+            (LEFT_WALL + tile.x * TILE_WIDTH + TILE_WIDTH / 2) as f32,
+            (TOP_WALL + tile.y * TILE_WIDTH + TILE_WIDTH / 2) as f32
+        )
+    }
+
+    // Finds the closest tile the layer can reach (by travel distance) that the player can't see.
+    // Returns None if there's nothing within max_dist tiles, e.g. in the open boss room.
+    pub fn find_cover(&self, start: Vec2<i32>, max_dist: i32, layer: MovementLayer) -> Option<Vec2<i32>> {
+        if self.cr_tiles.is_empty() {
+            return None;
+        }
+
+        let mut queue: VecDeque<(Vec2<i32>, i32)> = VecDeque::new();
+        let mut seen = vec![vec![false; ROOM_WIDTH as usize]; ROOM_HEIGHT as usize];
+
+        if start.x < 0 || start.x >= ROOM_WIDTH || start.y < 0 || start.y >= ROOM_HEIGHT {
+            return None;
+        }
+        seen[start.y as usize][start.x as usize] = true;
+        queue.push_back((start, 0));
+
+        while let Some((tile, dist)) = queue.pop_front() {
+            // Hiding right next to the player doesn't help anyone
+            let near_player = (tile.x - self.player_frame_tile.x).abs() <= 1 &&
+                (tile.y - self.player_frame_tile.y).abs() <= 1;
+            if !near_player && !self.player_in_sight(BlackBoard::tile_center(tile)) {
+                return Some(tile);
+            }
+
+            if dist >= max_dist {
+                continue;
+            }
+
+            let neighbors = [
+                Vec2::new(tile.x + 1, tile.y),
+                Vec2::new(tile.x - 1, tile.y),
+                Vec2::new(tile.x, tile.y - 1),
+                Vec2::new(tile.x, tile.y + 1),
+            ];
+            for next in neighbors.iter() {
+                if next.x >= 0 && next.x < ROOM_WIDTH && next.y >= 0 && next.y < ROOM_HEIGHT &&
+                !seen[next.y as usize][next.x as usize] && self.is_passable(*next, layer) {
+                    seen[next.y as usize][next.x as usize] = true;
+                    queue.push_back((*next, dist + 1));
+                }
+            }
+        }
+
+        None
     }
 
     pub fn set_boss_type(&mut self){
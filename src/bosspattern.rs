@@ -0,0 +1,256 @@
+use crate::blackboard::*;
+use crate::util::*;
+use crate::entity::*;
+use crate::behavior::*;
+use crate::attack::*;
+use crate::boxes::Box;
+use crate::finalenemy::*;
+use std::time::Instant;
+
+// Attack patterns for the final boss. The fight is split into phases by how much health the boss
+// has left, and each phase cycles through a list of patterns picked by blackboard.boss_kind (which
+// comes from the powerups the player took). Every pattern flashes first, then fires some volleys.
+
+// Fraction of max health each phase starts at
+pub const BOSS_PHASES: [f32; 3] = [1.0, 0.66, 0.33];
+// How long (ms) the boss is invulnerable while it changes phase
+pub const BOSS_PHASE_SHIFT_TIME: u128 = 1500;
+// How long (ms) it walks after the player between patterns, per phase
+pub const BOSS_CHASE_TIME: [u128; 3] = [1800, 1200, 700];
+// How far (radians) the sweep laser turns over the whole sweep, 100 degrees
+pub const SWEEP_ANGLE: f32 = std::f32::consts::PI * 100.0 / 180.0;
+// How far (radians) the spiral's arms turn each volley, 20 degrees
+pub const SPIRAL_TURN: f32 = std::f32::consts::PI / 9.0;
+// How far (px) the boss's side hitboxes sit to its left and right
+pub const BOSS_SIDE_OFFSET: f32 = 60.0;
+
+// Enemy component for the boss
+#[derive(Copy, Clone)]
+pub struct BossState {
+    pub side_box: Box, // Hitbox on either side of the boss, see side_positions
+    pub phase: usize, // Which of BOSS_PHASES the boss is in
+    pub phase_shift_time: Option<Instant>, // When the boss last changed phase
+    pub step: u32, // How many patterns it's been through this phase, picks the next one from the set
+    pub volleys: u32, // Volleys fired so far in the current pattern
+    pub sweep_aim: Option<Vec2<f32>>, // Where the player was when the current pattern started
+}
+
+impl BossState {
+    pub fn new() -> BossState {
+        BossState {
+            side_box: Box::new(Vec2::new(30, 70), Vec2::new(0, 0), Vec2::new(0, 0)),
+            phase: 0,
+            phase_shift_time: None,
+            step: 0,
+            volleys: 0,
+            sweep_aim: None,
+        }
+    }
+}
+
+// Where the boss's left and right side hitboxes are, they move with it
+pub fn side_positions(enemy: &Enemy) -> (Vec2<f32>, Vec2<f32>) {
+    (Vec2::new(enemy.pos.x - BOSS_SIDE_OFFSET, enemy.pos.y), Vec2::new(enemy.pos.x + BOSS_SIDE_OFFSET, enemy.pos.y))
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pattern {
+    RadialBurst, // Rings of shots in every direction
+    Spiral, // Arms of shots that turn a little every volley
+    SweepLaser, // A tight stream of shots sweeping across where the player was
+    GroundSlam, // Long windup, then two dense rings close together
+    AimedSpread, // The old three way shot at the player
+    Summon, // Calls in two minions
+}
+
+// How a pattern is timed: windup before the first volley, number of volleys and time between them
+pub struct PatternTiming {
+    pub telegraph: u128,
+    pub volleys: u32,
+    pub interval: u128,
+}
+
+pub fn pattern_timing(pattern: Pattern) -> PatternTiming {
+    match pattern {
+        Pattern::RadialBurst => PatternTiming { telegraph: 500, volleys: 3, interval: 450 },
+        Pattern::Spiral => PatternTiming { telegraph: 400, volleys: 24, interval: 90 },
+        Pattern::SweepLaser => PatternTiming { telegraph: 700, volleys: 30, interval: 35 },
+        Pattern::GroundSlam => PatternTiming { telegraph: 900, volleys: 2, interval: 250 },
+        Pattern::AimedSpread => PatternTiming { telegraph: 300, volleys: 3, interval: 400 },
+        Pattern::Summon => PatternTiming { telegraph: 600, volleys: 1, interval: 500 },
+    }
+}
+
+// What the boss does in each phase, depending on which kind it takes after
+pub fn pattern_set(boss_kind: EnemyKind, phase: usize) -> &'static [Pattern] {
+    match (boss_kind, phase) {
+        // Player went for speed, so the boss fills the room with fast bullets
+        (EnemyKind::Speed, 0) => &[Pattern::Spiral, Pattern::AimedSpread],
+        (EnemyKind::Speed, 1) => &[Pattern::Spiral, Pattern::SweepLaser, Pattern::Summon],
+        (EnemyKind::Speed, _) => &[Pattern::SweepLaser, Pattern::Spiral, Pattern::SweepLaser, Pattern::Summon],
+        // Player went for attack, so the boss drags the fight out with minions
+        (EnemyKind::Health, 0) => &[Pattern::RadialBurst, Pattern::Summon],
+        (EnemyKind::Health, 1) => &[Pattern::RadialBurst, Pattern::Spiral, Pattern::Summon],
+        (EnemyKind::Health, _) => &[Pattern::RadialBurst, Pattern::GroundSlam, Pattern::Spiral, Pattern::Summon],
+        // Player went for health, so the boss hits hard. Also covers boss_kind before set_boss_type has run
+        (_, 0) => &[Pattern::AimedSpread, Pattern::GroundSlam],
+        (_, 1) => &[Pattern::AimedSpread, Pattern::GroundSlam, Pattern::Summon, Pattern::RadialBurst],
+        (_, _) => &[Pattern::GroundSlam, Pattern::SweepLaser, Pattern::AimedSpread, Pattern::Summon],
+    }
+}
+
+// The pattern the boss is on right now
+pub fn current_pattern(enemy: &Enemy, blackboard: &BlackBoard) -> Pattern {
+    let (phase, step) = match enemy.boss {
+        Some( boss ) => (boss.phase, boss.step),
+        None => (0, 0),
+    };
+    let set = pattern_set(blackboard.boss_kind, phase);
+    set[step as usize % set.len()]
+}
+
+// Called when the boss stops chasing to start its next pattern
+pub fn start_pattern(enemy: & mut Enemy, blackboard: &BlackBoard){
+    // Sweeps are aimed once at the start so the player can step out of the way
+    let aim = aim_at_player(enemy, blackboard);
+    match enemy.boss {
+        Some( ref mut boss ) => {
+            boss.volleys = 0;
+            boss.sweep_aim = aim;
+        }
+        None => {}
+    }
+    enemy.state_timer = Instant::now();
+    enemy.state = State::Attack;
+}
+
+// Runs one frame of the current pattern. Returns true once it's finished.
+pub fn run_pattern(enemy: & mut Enemy, blackboard: &BlackBoard) -> bool {
+    let pattern = current_pattern(enemy, blackboard);
+    let timing = pattern_timing(pattern);
+    let elapsed = enemy.state_timer.elapsed().as_millis();
+
+    if elapsed < timing.telegraph {
+        enemy.telegraph = true;
+        return false;
+    }
+    enemy.telegraph = false;
+
+    let mut boss = match enemy.boss {
+        Some( boss ) => boss,
+        None => return true,
+    };
+
+    // Fire every volley that's come due, usually just the one
+    let active = elapsed - timing.telegraph;
+    while boss.volleys < timing.volleys && active >= boss.volleys as u128 * timing.interval {
+        fire_volley(enemy, blackboard, pattern, boss.volleys);
+        boss.volleys += 1;
+    }
+
+    let done = active >= timing.volleys as u128 * timing.interval;
+    if done {
+        boss.step += 1;
+        boss.sweep_aim = None;
+    }
+    enemy.boss = Some(boss);
+    done
+}
+
+// The volley each pattern fires. Summon doesn't shoot so it has none.
+pub fn pattern_emitter(pattern: Pattern) -> Option<Emitter> {
+    let interval = pattern_timing(pattern).interval;
+    let full = std::f32::consts::PI * 2.0;
+    match pattern {
+        // Every other ring is turned half a step so the gaps move
+        Pattern::RadialBurst => Some(Emitter { count: 12, spread: full, rotation: full / 24.0, delay: interval, behavior: None }),
+        // Three arms that weave as they go out
+        Pattern::Spiral => Some(Emitter { count: 3, spread: full, rotation: SPIRAL_TURN, delay: interval, behavior: Some(ProjectileBehavior::Sine(14.0, 40.0)) }),
+        // One shot a volley, turning across 100 degrees over the whole sweep. Starts slow and speeds up.
+        Pattern::SweepLaser => Some(Emitter {
+            count: 1,
+            spread: 0.0,
+            rotation: SWEEP_ANGLE / (pattern_timing(pattern).volleys - 1).max(1) as f32,
+            delay: interval,
+            behavior: Some(ProjectileBehavior::Accelerating(0.08, 7.0)),
+        }),
+        // Dense rings that break apart when they hit the walls
+        Pattern::GroundSlam => Some(Emitter { count: 20, spread: full, rotation: full / 40.0, delay: interval, behavior: Some(ProjectileBehavior::Splitting(2)) }),
+        // Three way shot at the player, 15 degrees apart, that bends after them for a bit
+        Pattern::AimedSpread => Some(Emitter { count: 3, spread: std::f32::consts::FRAC_PI_6, rotation: 0.0, delay: interval, behavior: Some(ProjectileBehavior::Homing(0.02)) }),
+        Pattern::Summon => None,
+    }
+}
+
+fn fire_volley(enemy: & mut Enemy, blackboard: &BlackBoard, pattern: Pattern, volley: u32){
+    let emitter = match pattern_emitter(pattern) {
+        Some( emitter ) => emitter,
+        None => {
+            summon_minions(enemy, blackboard);
+            return;
+        }
+    };
+    let aim = match pattern {
+        // Sweeps start 50 degrees to one side of where the player was when the pattern started
+        Pattern::SweepLaser => match enemy.boss {
+            Some( BossState { sweep_aim: Some( aim ), .. } ) => {
+                let start = aim.y.atan2(aim.x) - SWEEP_ANGLE / 2.0;
+                Vec2::new(start.cos(), start.sin())
+            }
+            _ => return,
+        },
+        Pattern::AimedSpread => match aim_at_player(enemy, blackboard) {
+            Some( aim ) => aim,
+            None => return,
+        },
+        _ => Vec2::new(1.0, 0.0),
+    };
+
+    // The boss fires the shots of the kind it takes after
+    let kind = match blackboard.boss_kind {
+        EnemyKind::Final => EnemyKind::Attack,
+        kind => kind,
+    };
+    fire_emitter_as(enemy, &emitter, aim, volley, kind);
+}
+
+// Boss can't be hurt while it's changing phase
+pub fn boss_invulnerable(enemy: &Enemy) -> bool {
+    match enemy.boss {
+        Some( BossState { phase_shift_time: Some( time ), .. } ) => time.elapsed().as_millis() < BOSS_PHASE_SHIFT_TIME,
+        _ => false,
+    }
+}
+
+// Called after the boss takes damage. Moves it into the next phase once its health drops far enough.
+pub fn check_phase(enemy: & mut Enemy){
+    let next = match enemy.boss {
+        Some( boss ) => boss.phase + 1,
+        None => return,
+    };
+    if next >= BOSS_PHASES.len() || enemy.hp > (enemy.m_hp as f32 * BOSS_PHASES[next]) as i32 {
+        return;
+    }
+
+    // One phase at a time, a big hit can't skip one
+    let lowest = if next + 1 < BOSS_PHASES.len() {
+        (enemy.m_hp as f32 * BOSS_PHASES[next + 1]) as i32 + 1
+    } else {
+        P_DEATH_HP + 1
+    };
+    enemy.hp = enemy.hp.max(lowest);
+
+    match enemy.boss {
+        Some( ref mut boss ) => {
+            boss.phase = next;
+            boss.phase_shift_time = Some(Instant::now());
+            boss.step = 0;
+            boss.volleys = 0;
+            boss.sweep_aim = None;
+        }
+        None => {}
+    }
+    enemy.telegraph = true;
+    enemy.state_timer = Instant::now();
+    enemy.state = State::Chase;
+}
@@ -12,6 +12,40 @@ use sdl2::rect::Rect;
     Walk,
 }*/
 
+// Who a box belongs to. Every box has one layer, and a mask of the layers it interacts with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layer {
+    Player,
+    Enemy,
+    PlayerProjectile,
+    EnemyProjectile,
+    Pickup,
+    Hazard,
+    Prop, // Crates and anything else that can be smashed
+}
+
+impl Layer {
+    // This layer as a single bit, masks are these ORed together
+    pub fn bit(&self) -> u32 {
+        1 << (*self as u32)
+    }
+
+    // Who this layer interacts with unless something changes it. The player can be hurt by enemies,
+    // their shots and hazards and picks things up. Enemies are hurt by the player's swings, bombs
+    // and shots but never by each other's shots.
+    pub fn default_mask(&self) -> u32 {
+        match self {
+            Layer::Player => Layer::Enemy.bit() | Layer::EnemyProjectile.bit() | Layer::Pickup.bit() | Layer::Hazard.bit() | Layer::Prop.bit(),
+            Layer::Enemy => Layer::Player.bit() | Layer::PlayerProjectile.bit(),
+            Layer::PlayerProjectile => Layer::Enemy.bit(),
+            Layer::EnemyProjectile => Layer::Player.bit(),
+            Layer::Pickup => Layer::Player.bit(),
+            Layer::Hazard => Layer::Player.bit(),
+            Layer::Prop => Layer::Player.bit(),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Box {
     pub hitbox: Vec2<u32>, // Hitbox where player takes damage.
@@ -20,6 +54,8 @@ pub struct Box {
     pub attack_x_offset: i32,
     pub attack_y_offset: i32,
     pub charge: bool,
+    pub layer: Layer, // Who this box belongs to
+    pub mask: u32, // Layers this box interacts with, see Layer::default_mask
 }
 
 impl Box {
@@ -31,8 +67,28 @@ impl Box {
             attack_x_offset: 20,
             attack_y_offset: -20,
             charge: false,
+            // Most boxes belong to enemies, the player and projectiles set their own with with_layer
+            layer: Layer::Enemy,
+            mask: Layer::Enemy.default_mask(),
         }
     }
+
+    // Same box on another layer, with that layer's usual mask
+    pub fn with_layer(mut self, layer: Layer) -> Box {
+        self.layer = layer;
+        self.mask = layer.default_mask();
+        self
+    }
+
+    // True if this box affects things on layer, e.g. an enemy shot checking it can hurt the player
+    pub fn interacts_with(&self, layer: Layer) -> bool {
+        self.mask & layer.bit() != 0
+    }
+
+    // True if this box affects other
+    pub fn collides_with(&self, other: &Box) -> bool {
+        self.interacts_with(other.layer)
+    }
     //need a function getter for each type of box
     pub fn get_walkbox(&self, pos : Vec2<f32>) -> Rect { Rect::new(
                                                     pos.x as i32 - (self.walkbox.x / 2) as i32,
@@ -56,8 +112,14 @@ impl Box {
 
         match dir {
             Direction::Up => {
-                Rect::new(pos.x as i32 - ( self.attackbox.x / 2 ) as i32, pos.y as i32 - (self.attackbox.y as i32) - (self.attackbox.y / 2 as i32) - 24,
-                        self.attackbox.x as u32, self.attackbox.y as u32)
+                if self.charge{
+                    // Starts right above the player's hitbox, the same way Down starts right below it
+                    Rect::new(pos.x as i32 - ( self.attackbox.x / 2 ) as i32, pos.y as i32 - 40 - self.attackbox.y,
+                            self.attackbox.x as u32, self.attackbox.y as u32)
+                } else {
+                    Rect::new(pos.x as i32 - ( self.attackbox.x / 2 ) as i32, pos.y as i32 - (self.attackbox.y as i32) - (self.attackbox.y / 2 as i32) - 24,
+                            self.attackbox.x as u32, self.attackbox.y as u32)
+                }
             }
             Direction::Down => {
                 Rect::new(pos.x as i32 - ( self.attackbox.x / 2 ) as i32, pos.y as i32 + 16,
@@ -0,0 +1,137 @@
+use crate::blackboard::*;
+use crate::util::*;
+use crate::entity::*;
+use crate::behavior::*;
+use crate::navigation::*;
+use std::time::Instant;
+
+// Charger. Walks up to the player, stops and flashes to telegraph, then dashes in a straight line
+// until it runs out of steam or slams into something. Dodging sideways during the flash beats it.
+
+// How close (px) the player has to be before it starts winding up
+pub const CHARGER_RANGE: f64 = 260.0;
+// Telegraph length (ms), the direction is locked in at the start of it
+pub const CHARGER_WINDUP_TIME: u128 = 600;
+// How long (ms) the dash lasts if it doesn't hit anything
+pub const CHARGER_DASH_TIME: u128 = 550;
+// Dash speed as a multiple of walking speed
+pub const CHARGER_DASH_MULT: f32 = 5.0;
+// How long (ms) it stands there dazed after a dash, longer if it hit a wall or rock
+pub const CHARGER_RECOVER_TIME: u128 = 500;
+pub const CHARGER_STUN_TIME: u128 = 1200;
+
+// Enemy component for chargers
+#[derive(Copy, Clone, Debug)]
+pub struct ChargerState {
+    pub dash: Option<Vec2<f32>>, // Direction it's locked into
+    pub slammed: bool, // The last dash ended in a wall or rock, so it's dazed for longer
+}
+
+impl ChargerState {
+    pub fn new() -> ChargerState {
+        ChargerState { dash: None, slammed: false }
+    }
+}
+
+pub struct ChargerBehavior;
+
+impl EnemyBehavior for ChargerBehavior {
+    // Attack is the windup and the dash
+    fn attack(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        let dash = match enemy.charger {
+            Some( ChargerState { dash: Some( dash ), .. } ) => dash,
+            _ => {
+                enemy.telegraph = false;
+                enemy.state = State::Chase;
+                return;
+            }
+        };
+
+        let elapsed = enemy.state_timer.elapsed().as_millis();
+        if elapsed < CHARGER_WINDUP_TIME {
+            enemy.telegraph = true;
+            return;
+        }
+        enemy.telegraph = false;
+
+        // Touching the player during the dash hurts, collision checks the attackbox while this is recent
+        enemy.last_attack_time = Some(Instant::now());
+
+        let step = enemy.speed * CHARGER_DASH_MULT * enemy.time_scale;
+        let next = Vec2::new(enemy.pos.x + dash.x * step, enemy.pos.y + dash.y * step);
+        let next_tile = tile_of(next);
+        let blocked = !blackboard.cr_tiles.is_empty() &&
+            (!in_bounds(next_tile) || !blackboard.is_passable(next_tile, enemy.layer));
+
+        if blocked {
+            stop_dash(enemy, CHARGER_STUN_TIME);
+            return;
+        }
+        enemy.pos = next;
+
+        if elapsed >= CHARGER_WINDUP_TIME + CHARGER_DASH_TIME {
+            stop_dash(enemy, CHARGER_RECOVER_TIME);
+        }
+    }
+
+    // Retreat is the daze after a dash, it just stands there
+    fn retreat(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        let slammed = match enemy.charger {
+            Some( charger ) => charger.slammed,
+            None => false,
+        };
+        let recover = if slammed { CHARGER_STUN_TIME } else { CHARGER_RECOVER_TIME };
+        if enemy.state_timer.elapsed().as_millis() >= recover {
+            set_dash(enemy, None, false);
+            enemy.state = State::Chase;
+        }
+    }
+
+    fn chase(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if dodge_charge(enemy, blackboard, false) {
+            return;
+        }
+
+        chase_player(enemy, blackboard);
+
+        // Only commit when there's a straight line to the player
+        if Enemy::distance_to_player(enemy, blackboard) < CHARGER_RANGE && Enemy::player_in_sight(enemy, blackboard) {
+            match aim_at_player(enemy, blackboard) {
+                Some( aim ) => {
+                    set_dash(enemy, Some(aim), false);
+                    enemy.dir = dir_from_vec(aim, enemy.dir);
+                    enemy.state_timer = Instant::now();
+                    enemy.state = State::Attack;
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn heal(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.state = State::Chase;
+    }
+
+    fn sight_range(&self) -> f64 { 350.0 }
+}
+
+// Ends the dash and dazes the charger for a while
+fn stop_dash(enemy: & mut Enemy, recover: u128){
+    enemy.telegraph = false;
+    // Dazed chargers don't hurt to touch
+    enemy.last_attack_time = None;
+    enemy.state_timer = Instant::now();
+    // Remember whether it slammed into something so Retreat knows how long to wait
+    set_dash(enemy, None, recover == CHARGER_STUN_TIME);
+    enemy.state = State::Retreat;
+}
+
+fn set_dash(enemy: & mut Enemy, dash: Option<Vec2<f32>>, slammed: bool){
+    match enemy.charger {
+        Some( ref mut charger ) => {
+            charger.dash = dash;
+            charger.slammed = slammed;
+        }
+        None => {}
+    }
+}
@@ -2,114 +2,184 @@ use crate::game::*;
 use crate::util::*;
 //use crate::player::PowerUp;
 use crate::entity::*;
+use crate::enemydef::*;
+use crate::elite::*;
+use crate::miniboss::*;
+use crate::loot::*;
 use crate::tile::*;
+use crate::room::{ROOM_WIDTH, ROOM_HEIGHT};
 use crate::menu::*;
 use crate::blackboard::*;
+use crate::spatial::*;
+use crate::knockback::{self, *};
+use crate::boxes::Layer;
+use crate::attack::AtkImpact;
+use crate::player::CHARGE_STRIKE_TIME;
 use std::time::Duration;
 use sdl2::rect::Rect;
-use sdl2::pixels::Color;
-use roguelike::SDLCore;
 //use crate::boxes::*;
 
-pub fn enemy_collision(enemy: &mut Enemy, x: &i32, y: &i32) {
-
-
-    let intersection = enemy.box_es.get_walkbox(enemy.pos).intersection(Rect::new(
-        LEFT_WALL + x * TILE_WIDTH,
-        TOP_WALL + y * TILE_WIDTH,
-        TILE_WIDTH as u32,
-        TILE_WIDTH as u32
-    ));
-
-    let inter_rect = match intersection {
-        Some(x) => x,
-        None => return,
-    };
+// Longest step (px) taken at once when moving something, shorter than the thinnest walkbox so
+// nothing can skip over a tile edge between checks
+pub const MAX_SUBSTEP: f32 = 8.0;
+
+// Tile collision shared by the player and enemies. A move is split into small steps, and each step
+// moves along x first and then y, pushing the walkbox back out to the edge of any tile it ran
+// into on that axis. Because only one axis is ever fixed at a time, corners don't jitter and
+// running into a wall at an angle slides along it.
+
+type Tiles = Vec<Vec<std::boxed::Box<dyn Tile>>>;
+
+// Tile range (x0, y0, x1, y1) a walkbox of size centred at pos covers, same rounding as get_walkbox
+fn covered_tiles(pos: Vec2<f32>, size: Vec2<u32>) -> (i32, i32, i32, i32) {
+    let left = pos.x as i32 - (size.x / 2) as i32;
+    let top = pos.y as i32 - (size.y / 2) as i32;
+    (
+        (left - LEFT_WALL).div_euclid(TILE_WIDTH),
+        (top - TOP_WALL).div_euclid(TILE_WIDTH),
+        (left + size.x as i32 - 1 - LEFT_WALL).div_euclid(TILE_WIDTH),
+        (top + size.y as i32 - 1 - TOP_WALL).div_euclid(TILE_WIDTH),
+    )
+}
 
-    //println!{"Collision"};
+// True if the tile can't be entered. Anything off the tile grid counts as blocked.
+fn tile_blocked(tiles: &Tiles, x: i32, y: i32, blocked: &dyn Fn(&Walkability) -> bool) -> bool {
+    if x < 0 || y < 0 || x >= ROOM_WIDTH || y >= ROOM_HEIGHT {
+        return true;
+    }
+    blocked(&tiles[y as usize][x as usize].walkability())
+}
 
-    let mut x_offset = inter_rect.width() as i32;
-    let mut y_offset = inter_rect.height() as i32;
+// Pushes the walkbox out along x after it moved by dx. Moving right it ends flush with the left
+// side of the tile it hit, moving left flush with the right side.
+fn resolve_x(tiles: &Tiles, pos: & mut Vec2<f32>, dx: f32, size: Vec2<u32>, blocked: &dyn Fn(&Walkability) -> bool) {
+    if dx == 0.0 {
+        return;
+    }
+    let (x0, y0, x1, y1) = covered_tiles(*pos, size);
+    for y in y0..=y1 {
+        if dx > 0.0 && tile_blocked(tiles, x1, y, blocked) {
+            pos.x = (LEFT_WALL + x1 * TILE_WIDTH - (size.x - size.x / 2) as i32) as f32;
+            return;
+        }
+        if dx < 0.0 && tile_blocked(tiles, x0, y, blocked) {
+            pos.x = (LEFT_WALL + (x0 + 1) * TILE_WIDTH + (size.x / 2) as i32) as f32;
+            return;
+        }
+    }
+}
 
-    if enemy.pos.x < inter_rect.x() as f32 && //To left
-    enemy.pos.y < inter_rect.y() as f32 { //And Above
-        match enemy.dir{
-            Direction::Down | Direction::Up => { //Act like it is only to the left
-                y_offset = 0;
-            }
-            Direction::Right | Direction::Left => { //Act like it is only above
-                x_offset = 0;
-            }
-            /*_ => {
-                println!("What? This shouldn't be happening");
-            }*/
+// Same as resolve_x for the y axis
+fn resolve_y(tiles: &Tiles, pos: & mut Vec2<f32>, dy: f32, size: Vec2<u32>, blocked: &dyn Fn(&Walkability) -> bool) {
+    if dy == 0.0 {
+        return;
+    }
+    let (x0, y0, x1, y1) = covered_tiles(*pos, size);
+    for x in x0..=x1 {
+        if dy > 0.0 && tile_blocked(tiles, x, y1, blocked) {
+            pos.y = (TOP_WALL + y1 * TILE_WIDTH - (size.y - size.y / 2) as i32) as f32;
+            return;
         }
-    }else if enemy.pos.x < inter_rect.x() as f32 && //To left
-    enemy.pos.y > (inter_rect.y() + inter_rect.height() as i32) as f32{ //And below
-        match enemy.dir{
-            Direction::Up | Direction::Down=> { //Act like it is only to left
-                y_offset = 0;
-            }
-            Direction::Right | Direction::Left => { //Act like it is only below
-                x_offset = 0;
-                y_offset *= -1;
-            }
-            /*_ => {
-                println!("What? I think I left the stove on");
-            }*/
+        if dy < 0.0 && tile_blocked(tiles, x, y0, blocked) {
+            pos.y = (TOP_WALL + (y0 + 1) * TILE_WIDTH + (size.y / 2) as i32) as f32;
+            return;
         }
-    }else if enemy.pos.x > (inter_rect.x() + inter_rect.width() as i32) as f32  &&//To right
-    enemy.pos.y < inter_rect.y() as f32 { //And above
-        match enemy.dir{
-            Direction::Down | Direction::Up=> { //Act like it is only to right
-                x_offset *= -1;
-                y_offset = 0;
+    }
+}
+
+// True if a walkbox of size centred at pos overlaps any blocked tile
+pub fn box_blocked(tiles: &Tiles, pos: Vec2<f32>, size: Vec2<u32>, blocked: &dyn Fn(&Walkability) -> bool) -> bool {
+    let (x0, y0, x1, y1) = covered_tiles(pos, size);
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            if tile_blocked(tiles, x, y, blocked) {
+                return true;
             }
-            Direction::Left | Direction::Right => { //Act like it is only above
-                x_offset = 0;
+        }
+    }
+    false
+}
+
+// Gets a walkbox that's already inside blocked tiles (spawned there, knocked in) back out. Each
+// pass pushes it out of the tile it overlaps most, along whichever axis is the shorter way out.
+fn push_out(tiles: &Tiles, pos: Vec2<f32>, size: Vec2<u32>, blocked: &dyn Fn(&Walkability) -> bool) -> Vec2<f32> {
+    let mut pos = pos;
+    for _ in 0..4 {
+        let walkbox = Rect::new(
+            pos.x as i32 - (size.x / 2) as i32,
+            pos.y as i32 - (size.y / 2) as i32,
+            size.x,
+            size.y
+        );
+        let (x0, y0, x1, y1) = covered_tiles(pos, size);
+        let mut worst: Option<(Rect, Rect)> = None;
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if !tile_blocked(tiles, x, y, blocked) {
+                    continue;
+                }
+                let tile = Rect::new(LEFT_WALL + x * TILE_WIDTH, TOP_WALL + y * TILE_WIDTH, TILE_WIDTH as u32, TILE_WIDTH as u32);
+                match walkbox.intersection(tile) {
+                    Some( overlap ) => {
+                        let bigger = match worst {
+                            Some( (most, _) ) => overlap.width() * overlap.height() > most.width() * most.height(),
+                            None => true,
+                        };
+                        if bigger {
+                            worst = Some((overlap, tile));
+                        }
+                    }
+                    None => {}
+                }
             }
-            /*_ => {
-                println!("What? Is life even real?");
-            }*/
         }
-    }else if enemy.pos.x > (inter_rect.x() + inter_rect.width() as i32) as f32 &&//To right
-    enemy.pos.y > (inter_rect.y() + inter_rect.height() as i32) as f32{ //And below
-        match enemy.dir{
-            Direction::Up|Direction::Down => { //Act like it is only to right
-                x_offset *= -1;
-                y_offset = 0;
+        let (overlap, tile) = match worst {
+            Some( w ) => w,
+            None => break,
+        };
+        if overlap.width() <= overlap.height() {
+            if pos.x < tile.center().x() as f32 {
+                pos.x -= overlap.width() as f32;
+            } else {
+                pos.x += overlap.width() as f32;
             }
-            Direction::Left | Direction::Right => { //Act like it is only below
-                x_offset = 0;
-                y_offset *= -1;
+        } else {
+            if pos.y < tile.center().y() as f32 {
+                pos.y -= overlap.height() as f32;
+            } else {
+                pos.y += overlap.height() as f32;
             }
-            /*_ => {
-                println!("What? I think I might be in hell");
-            }*/
         }
-
-    }else if enemy.pos.x < inter_rect.x() as f32 {
-        // TO THE LEFT OF ROCK
-        y_offset = 0;
-    }else if enemy.pos.x > (inter_rect.x() + inter_rect.width() as i32) as f32 {
-        // TO THE RIGHT OF ROCK
-        x_offset *= -1;
-        y_offset = 0;
-    }else if enemy.pos.y < inter_rect.y() as f32 {
-        // ABOVE ROCK
-        x_offset = 0;
-    }else if enemy.pos.y > (inter_rect.y() + inter_rect.height() as i32) as f32 {
-        // BELOW ROCK
-        x_offset = 0;
-        y_offset *= -1;
     }
+    pos
+}
 
-    //println!("Offset for X BEFORE: {}, for Y: {}", x_offset, y_offset);
-    enemy.pos.x -= x_offset as f32;
-    enemy.pos.y -= y_offset as f32;
+// Moves a walkbox of size from start to end, stopping it against any tile blocked says it can't
+// enter and sliding it along whatever it hit. Returns where it ends up.
+pub fn move_and_collide(tiles: &Tiles, start: Vec2<f32>, end: Vec2<f32>, size: Vec2<u32>, blocked: &dyn Fn(&Walkability) -> bool) -> Vec2<f32> {
+    if tiles.is_empty() {
+        return end;
+    }
+    // Already stuck in something, finish the move and then get it out
+    if box_blocked(tiles, start, size, blocked) {
+        return push_out(tiles, end, size, blocked);
+    }
+    let delta = Vec2::new(end.x - start.x, end.y - start.y);
+    let length = (delta.x * delta.x + delta.y * delta.y).sqrt();
+    let steps = (length / MAX_SUBSTEP).ceil().max(1.0) as u32;
+    let step = Vec2::new(delta.x / steps as f32, delta.y / steps as f32);
+
+    let mut pos = start;
+    for _ in 0..steps {
+        pos.x += step.x;
+        resolve_x(tiles, & mut pos, step.x, size, blocked);
+        pos.y += step.y;
+        resolve_y(tiles, & mut pos, step.y, size, blocked);
+    }
+    pos
 }
 
-pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, blackboard: &BlackBoard) {
+pub fn base(game : &mut Game, menu : &mut MenuState, blackboard: &BlackBoard) {
 // Outermost wall collision
         game.player.pos.x = game.player.pos.x.clamp(
             LEFT_WALL as f32 + (game.player.box_es.walkbox.x/2) as f32,
@@ -120,33 +190,51 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, blackb
             BOT_WALL as f32 - (game.player.box_es.walkbox.y/2) as f32
         );
 
-    // More robust clamping for walls
-        if game.player.pos.y < TOP_WALL as f32 + 5.0 * 64.0 || game.player.pos.y > BOT_WALL as f32 - 5.0 * 64.0 {
-            game.player.pos.x = game.player.pos.x.clamp(
-                LEFT_WALL as f32 + 64.0 + (game.player.box_es.walkbox.x/2) as f32,
-                RIGHT_WALL as f32 - 64.0 - (game.player.box_es.walkbox.x/2) as f32
-            );
-        }
-    if game.player.pos.x < LEFT_WALL as f32 + 8.0 * 64.0 || game.player.pos.x > RIGHT_WALL as f32 - 8.0 * 64.0 {
-        game.player.pos.y = game.player.pos.y.clamp(
-            TOP_WALL as f32 + 64.0 + (game.player.box_es.walkbox.x/2) as f32,
-            BOT_WALL as f32 - 64.0 - (game.player.box_es.walkbox.x/2) as f32
-        );
-    }
+    // Replay this frame's moves against the tiles, walls and doors included
+        let player_start = game.player.lastpos;
+        let player_end = game.player.pos;
+        let player_size = game.player.box_es.walkbox;
+        game.player.pos = move_and_collide(&game.current_room().tiles, player_start, player_end, player_size,
+            &|w| MovementLayer::Walker.blocked_by(w));
+        // Crates and other solid props
+        let player_end = game.player.pos;
+        game.player.pos = game.current_room().props.block(player_start, player_end, player_size);
 
     // Maintain enemy bounds for the room and check player collisions
-        let mut enemy_list = game.current_room().enemies.clone();
-
+        // Whether the room was already clear going into this frame, the list is taken out of the room below
+        let room_was_clear = BlackBoard::get_enemy_quantity(game) == 0;
+        // Moved out (not copied) while the room's tiles get changed below, and moved straight back at
+        // the end. Nothing else touches the room's enemies in between, and every handle stays valid.
+        let mut enemy_list = std::mem::take(&mut game.current_room_mut().enemies);
+        // Grid ids are positions in here, the same order iter_mut goes in
+        let handles = enemy_list.handles();
+
+        // Counted before anything moves so an enemy knocked into a pit this frame still counts as
+        // the last one alive
         let mut live_count = 0;
         for enemy in enemy_list.iter_mut() {
-            if enemy.death == false{
+            if enemy.death == false || enemy.has_pending_spawns() {
                 live_count += 1;
             }
         }
 
         for enemy in enemy_list.iter_mut() {
+            if enemy.death {
+                continue;
+            }
+            let layer = enemy.layer;
+            // A shove can carry walkers out over a pit, they fall in below
+            let knocked = knockback::knocked(enemy);
+            enemy.pos = move_and_collide(&game.current_room().tiles, enemy.lastpos, enemy.pos, enemy.box_es.walkbox,
+                &|w| layer.blocked_by(w) && !(knocked && *w == Walkability::Pit));
+            // Crates stop walkers too, flyers and phasers go over them
+            if layer == MovementLayer::Walker {
+                enemy.pos = game.current_room().props.block(enemy.lastpos, enemy.pos, enemy.box_es.walkbox);
+            }
+            if knocked {
+                knockback::land_on_hazard(enemy, &game.current_room().tiles);
+            }
 
-            enemy.lastpos = enemy.pos; //Update the last position
             enemy.pos.x = enemy.pos.x.clamp(
                 (LEFT_WALL as f32 + (enemy.box_es.walkbox.x * 4) as f32) - TILE_WIDTH as f32,
                 (RIGHT_WALL as f32 - (enemy.box_es.walkbox.x * 4) as f32) + TILE_WIDTH as f32
@@ -155,38 +243,90 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, blackb
                 (TOP_WALL as f32 + (enemy.box_es.walkbox.y * 4) as f32) - TILE_WIDTH as f32,
                 (BOT_WALL as f32 - (enemy.box_es.walkbox.y * 4) as f32) + TILE_WIDTH as f32
             );
+        }
+
+
+        let mut time_since_attack = Duration::new(69, 420); // haha funny number
+        match game.player.last_attack_time{
+            Some(time) =>{
+                time_since_attack = time.elapsed();
+            },
+            None=>{
+
+            }
+        }
+        // Attempt at collision with attackbox
+        let mut player_attack = game.player.box_es.get_attackbox(game.player.pos, game.player.dir);
+        let swinging = game.player.is_attacking && time_since_attack.as_millis() % 250 > 100 && !game.player.is_charging ||
+            game.player.is_charging && time_since_attack.as_millis() > 50 && time_since_attack.as_millis() <= CHARGE_STRIKE_TIME as u128;
+        // A full charge goes through shields
+        let piercing = swinging && game.player.is_charging && game.player.full_charge;
+        let swing_force = if game.player.is_charging { CHARGE_KNOCKBACK } else { SWING_KNOCKBACK };
+        if swinging && game.player.is_charging {
+            game.player.charge_box.charge = true;
+            player_attack = game.player.charge_box.get_attackbox(game.player.pos, game.player.dir);
+        }
+        let player_bomb = game.player.box_es.get_bombbox(game.player.pos_static, game.player.dir);
+
+        // Swings and bombs smash props too
+        let player_box = game.player.box_es;
+        let player_damage = game.player.swing_damage();
+        if swinging {
+            game.current_room_mut().props.damage_in(player_attack, &player_box, player_damage);
+        }
+        if game.player.using_bomb {
+            game.current_room_mut().props.damage_in(player_bomb, &player_box, 4);
+        }
+
+        // Broad phase, only enemies sharing a cell with the swing or the bomb get a real test
+        let mut enemy_grid = SpatialGrid::new();
+        for (i, handle) in handles.iter().enumerate() {
+            let enemy = &enemy_list[*handle];
+            if !enemy.death {
+                enemy_grid.insert(i, enemy.box_es.get_hitbox(enemy.pos));
+            }
+        }
+        let mut near_swing = vec![false; enemy_list.len()];
+        if swinging {
+            for i in enemy_grid.query(player_attack) {
+                near_swing[i] = true;
+            }
+        }
+        let mut near_bomb = vec![false; enemy_list.len()];
+        if game.player.using_bomb {
+            for i in enemy_grid.query(player_bomb) {
+                near_bomb[i] = true;
+            }
+        }
+
+        for (index, enemy) in enemy_list.iter_mut().enumerate() {
 
             let player_test = game.player.box_es.get_hitbox(game.player.pos);
             // If the test enemy is in the current room of the player...
 
             //handles two + enemies dying at once for power up, spawns random power up from enemy types in room
-            if game.current_room().gem_count != 1 &&  BlackBoard::get_enemy_quantity(game) == 0 {
+            if game.current_room().gem_count != 1 &&  room_was_clear {
                 game.current_room_mut().increment_gem();
                 game.current_room_mut()
                     .tile_at(288, 100)
-                    .place_gem(match enemy.kind {
-                        EnemyKind::Health => Gem::Red,
-                        EnemyKind::Speed => Gem::Blue,
-                        EnemyKind::Attack => Gem::Yellow,
-                        EnemyKind::Final => Gem::None,
-                    });
+                    .place_gem(enemy_def(enemy.kind).gem);
             }
 
 
             // FINAL BOSS projectile (no it isn't, it works for all projectiles)
-            //enemy.move_projectile(&game.current_room().tile_at(atk.pos.x, atk.pos.y));
-            enemy.move_projectile();
+            let player_pos = game.player.pos;
+            enemy.move_projectile(game.current_room(), player_pos);
             if !enemy.death() {
 
 
                 //If enemy is attacking
-                if enemy.recently_attacked() {
+                if enemy.recently_attacked() && enemy.box_es.collides_with(&game.player.box_es) {
                     //See if player collides with attackbox
                     let enemy_attack = enemy.box_es.get_attackbox(enemy.pos, enemy.dir);
                     if player_test.has_intersection(enemy_attack) {
                         //Enemy attacked player
                         //game.player.take_damage(1, P_INVINCIBILITY_TIME);
-                        game.player.take_damage(enemy.attack_damage(), P_INVINCIBILITY_TIME);
+                        game.player.take_hit(enemy.attack_damage(), P_INVINCIBILITY_TIME, enemy.pos, PLAYER_KNOCKBACK);
                         if game.player.death() {
                             *menu = MenuState::GameOver;
                         }
@@ -196,53 +336,27 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, blackb
                 // If the test enemy's walkbox intersects with the player walkbox...
                 let wb_test = enemy.box_es.get_hitbox(enemy.pos);
 
-                let mut time_since_attack = Duration::new(69, 420); // haha funny number
-                match game.player.last_attack_time{
-                    Some(time) =>{
-                        time_since_attack = time.elapsed();
-                    },
-                    None=>{
-
-                    }
-                }
-                // Attempt at collision with attackbox
-                let mut player_attack = game.player.box_es.get_attackbox(game.player.pos, game.player.dir);
-                if game.player.is_attacking && time_since_attack.as_millis() % 250 > 100 && !game.player.is_charging || time_since_attack.as_millis() % 1000 > 800 && game.player.is_charging{
-                    if game.player.is_charging {
-                        game.player.charge_box.charge = true;
-                        player_attack = game.player.charge_box.get_attackbox(game.player.pos, game.player.dir);
-                    }
+                if near_swing[index] && game.player.box_es.collides_with(&enemy.box_es) {
                     //let player_attack = game.player.get_attackbox_world();
-                    if wb_test.has_intersection(player_attack) {
-                        enemy.take_damage(game.player.attack, E_INVINCIBILITY_TIME);
+                    // Shielded elites shrug off swings from the front
+                    if wb_test.has_intersection(player_attack) && (piercing || !shield_blocks(enemy, game.player.pos)) {
+                        enemy.take_hit(player_damage, E_INVINCIBILITY_TIME, game.player.pos, swing_force);
                         //edge case for enemies dying for power up
-                        if game.current_room().gem_count != 1 &&  BlackBoard::get_enemy_quantity(game) == 0 {
+                        if game.current_room().gem_count != 1 &&  room_was_clear {
                             enemy.power = true;
                         }
-                        //main case to determine power up
-                        if enemy.death == true && (live_count == 1 || blackboard.boss_fight) {
-                            enemy.power = true;
-                            game.current_room_mut().increment_gem();
-                        }
                         //executes if power up is true meaning a power up should be dropped as its the last enemy
                         if enemy.power == true {
                             // Place gem on enemy's current tile.
                             // TODO: Factor in walkability for tile that the gem drops on.
                             game.current_room_mut()
                                 .tile_at(enemy.get_pos_x(), enemy.get_pos_y())
-                                .place_gem(match enemy.kind {
-                                    EnemyKind::Health => Gem::Red,
-                                    EnemyKind::Speed => Gem::Blue,
-                                    EnemyKind::Attack => Gem::Yellow,
-                                    EnemyKind::Final => Gem::None,
-                                });
+                                .place_gem(enemy_def(enemy.kind).gem);
                             enemy.power = false;
                         }
                     }
                 }
                 if game.player.using_bomb {
-                    let player_bomb = game.player.box_es.get_bombbox(game.player.pos_static, game.player.dir);
-
                     // Used to blow up door on opposite side
                     let mut x_off = 0;
                     let mut y_off = 0;
@@ -293,32 +407,14 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, blackb
                         }
                     }
 
-                    if wb_test.has_intersection(player_bomb) {
+                    if near_bomb[index] && game.player.box_es.collides_with(&enemy.box_es) && wb_test.has_intersection(player_bomb) {
                         //println!("Bomb collided with enemy!");
-                        enemy.take_damage(4, E_INVINCIBILITY_TIME); //Bomb deals 3 damage
+                        let bomb_centre = Vec2::new(player_bomb.center().x() as f32, player_bomb.center().y() as f32);
+                        enemy.take_hit(4, E_INVINCIBILITY_TIME, bomb_centre, BOMB_KNOCKBACK); //Bomb deals 3 damage
                         //println!("damage done was 3 from bomb");
-                        if enemy.death == true && live_count == 1
-                        {
-                            enemy.power = true;
-                        }
-                        if enemy.power == true {
-                            // Place gem on enemy's current tile.
-                            // TODO: Factor in walkability for tile that the gem drops on.
-                            game.current_room_mut()
-                                .tile_at(enemy.get_pos_x(), enemy.get_pos_y())
-                                .place_gem(match enemy.kind {
-                                    EnemyKind::Health => Gem::Red,
-                                    EnemyKind::Speed => Gem::Blue,
-                                    EnemyKind::Attack => Gem::Yellow,
-                                    EnemyKind::Final => Gem::None,
-                                });
-
-                            enemy.power = false;
-                        }
                     }
                 }
 
-
                 // Then there's a collision!
                 /*if wb_test.has_intersection(player_test) {
                     //Damage enemy also! For some reason
@@ -335,93 +431,95 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, blackb
                 }*/
             }
 
-            for atk in &enemy.atk_list{
-                // let cur = Vec2::new(
-                //     (atk.pos.x as i32 - LEFT_WALL) / TILE_WIDTH,
-                //     (atk.pos.y as i32 - TOP_WALL) / TILE_WIDTH
-                // );
-                //let current_tile = &mut game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].tiles[((atk.pos.y as i32 - TOP_WALL) / TILE_WIDTH) as usize][((atk.pos.x as i32 - LEFT_WALL) / TILE_WIDTH) as usize];
-                //let current_tile = &mut game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].tile_at(atk.pos.x as i32, atk.pos.y as i32);
-                //let current_tile = &mut game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].tile_at(cur.x, cur.y);
-                //println!("{:?}", current_tile.walkability());
-                let wb_test = atk.box_es.get_hitbox(atk.pos);
-                let player_test = game.player.box_es.get_hitbox(game.player.pos);
-
-                if wb_test.has_intersection(player_test){
-                    game.player.take_damage(atk.damage, P_INVINCIBILITY_TIME);
-                    if game.player.death() {
-                        *menu = MenuState::GameOver;
-                    }
+            // Runs once on the frame an enemy dies, whatever killed it: a swing, a bomb, or being
+            // knocked into a pit or onto spikes
+            if enemy.death && !enemy.loot_dropped {
+                enemy.loot_dropped = true;
+                // Every enemy rolls its loot table
+                drop_enemy_loot(game.current_room_mut(), enemy);
+                //main case to determine power up
+                if (live_count == 1 && !enemy.has_pending_spawns()) || blackboard.boss_fight {
+                    enemy.power = true;
+                    game.current_room_mut().increment_gem();
+                }
+                // Elites always drop their gem
+                if enemy.elite.is_some() {
+                    enemy.power = true;
+                }
+                if enemy.power {
+                    // Place gem on enemy's current tile.
+                    // TODO: Factor in walkability for tile that the gem drops on.
+                    game.current_room_mut()
+                        .tile_at(enemy.get_pos_x(), enemy.get_pos_y())
+                        .place_gem(enemy_def(enemy.kind).gem);
+                    enemy.power = false;
+                }
+                // Beating a mini-boss always pays out
+                if enemy.mini_boss {
+                    drop_mini_boss_reward(game.current_room_mut(), enemy);
                 }
             }
         }
 
-
-
-
-        core.wincan.set_draw_color(Color::RGBA(128, 0, 0, 255));
-        let mut x = 0;
-        let mut y = 0;
-        // This can't be done with the current room function bc it returns a reference which messes up internal stuff
-        for row in &game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].tiles {
-            for t in row {
-                match t.walkability() {
-                    Walkability::Wall | Walkability::Rock | Walkability::Pit => {
-                        // Hacky af block collision that needs to be changed later
-                        let opt = game.player.box_es.get_walkbox(game.player.pos).intersection(Rect::new(
-                            LEFT_WALL + x * TILE_WIDTH,
-                            TOP_WALL + y * TILE_WIDTH,
-                            TILE_WIDTH as u32,
-                            TILE_WIDTH as u32
-                        ));
-                        for enemy in enemy_list.iter_mut() {
-                            match enemy.kind {
-                                EnemyKind::Speed => {},
-                                _ => enemy_collision(enemy, &x, &y)
-                            }
-                        }
-                        // increment x
-                        // if we do this later it messes thing up due to the continue statement in
-                        // the unboxing
-                        x += 1;
-
-                        let inter_rect = match opt {
-                            Some(x) => x,
-                            None => continue, // If no intersection just leave function, we're done
-                        };
-                        let mut x_offset = inter_rect.width() as i32;
-                        let mut y_offset = inter_rect.height() as i32;
-
-                        if game.player.pos.x < inter_rect.x() as f32 {
-                            // TO THE LEFT OF ROCK
-                            y_offset = 0;
-                        }
-                        if game.player.pos.x > (inter_rect.x() + inter_rect.width() as i32) as f32 {
-                            // TO THE RIGHT OF ROCK
-                            x_offset *= -1;
-                            y_offset = 0;
-                        }
-                        if game.player.pos.y < inter_rect.y() as f32 {
-                            // ABOVE ROCK
-                            x_offset = 0;
-                        }
-                        if game.player.pos.y > (inter_rect.y() + inter_rect.height() as i32) as f32 {
-                            // BELOW ROCK
-                            x_offset = 0;
-                            y_offset *= -1;
-                        }
-
-                        game.player.pos.x -= x_offset as f32;
-                        game.player.pos.y -= y_offset as f32;
-                    }
-
-                    _ => x += 1,
+        // Projectiles against the player, only ones sharing a cell with the player get a real test
+        let mut shots = Vec::new();
+        let mut shot_grid = SpatialGrid::new();
+        for (handle, enemy) in enemy_list.iter_with_handles() {
+            for (j, atk) in enemy.atk_list.iter().enumerate() {
+                shot_grid.insert(shots.len(), atk.box_es.get_hitbox(atk.pos));
+                shots.push((handle, j));
+            }
+        }
+        // A swing knocks shots it catches back the way they came, onto the player's side
+        if swinging {
+            for shot in shot_grid.query(player_attack) {
+                let (owner, j) = shots[shot];
+                let atk = &mut enemy_list[owner].atk_list[j];
+                if atk.reflectable() && atk.box_es.get_hitbox(atk.pos).has_intersection(player_attack) {
+                    atk.reflect();
                 }
             }
+        }
+        // Rolling goes straight through them
+        let player_test = game.player.box_es.get_hitbox(game.player.pos);
+        let near_player = if game.player.dodging() { Vec::new() } else { shot_grid.query(player_test) };
+        for shot in near_player {
+            let (owner, j) = shots[shot];
+            let atk = &enemy_list[owner].atk_list[j];
+            if atk.box_es.collides_with(&game.player.box_es) && atk.box_es.get_hitbox(atk.pos).has_intersection(player_test){
+                game.player.take_hit(atk.damage, P_INVINCIBILITY_TIME, atk.pos, SHOT_KNOCKBACK);
+                if game.player.death() {
+                    *menu = MenuState::GameOver;
+                }
+            }
+        }
 
-            // Prepare for next iteration of loop
-            y += 1;
-            x = 0;
+        // Projectiles against enemies. Enemy shots are masked off from enemies so this only finds
+        // the ones the player reflected above.
+        let mut spent = Vec::new();
+        for (shot, &(owner, j)) in shots.iter().enumerate() {
+            let atk = &enemy_list[owner].atk_list[j];
+            if !atk.box_es.interacts_with(Layer::Enemy) {
+                continue;
+            }
+            let atk_test = atk.box_es.get_hitbox(atk.pos);
+            for target in enemy_grid.query(atk_test) {
+                let enemy = &enemy_list[handles[target]];
+                if !enemy.death && atk.box_es.collides_with(&enemy.box_es) &&
+                enemy.box_es.get_hitbox(enemy.pos).has_intersection(atk_test) {
+                    spent.push((shot, handles[target]));
+                    break;
+                }
+            }
+        }
+        // Backwards so removing a shot doesn't move the ones still to go
+        for &(shot, target) in spent.iter().rev() {
+            let (owner, j) = shots[shot];
+            let damage = enemy_list[owner].atk_list[j].damage;
+            let from = enemy_list[owner].atk_list[j].pos;
+            enemy_list[target].take_hit(damage, E_INVINCIBILITY_TIME, from, SHOT_KNOCKBACK);
+            let atk = enemy_list[owner].atk_list.remove(j);
+            enemy_list[owner].atk_impacts.push(AtkImpact::new(&atk));
         }
 
         game.current_room_mut().enemies = enemy_list;
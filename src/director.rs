@@ -0,0 +1,216 @@
+use crate::game::*;
+use crate::util::*;
+use crate::entity::*;
+use crate::room::*;
+use crate::blackboard::*;
+use crate::loot::*;
+use crate::floor::room_enemy;
+use crate::navigation::tile_of;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+// Difficulty director. Watches how the last few rooms went (damage taken, how long they took to
+// clear and where the player's health is heading) and nudges the next rooms to match. A player
+// who is struggling gets fewer, slower enemies and more hearts, one who is cruising gets more
+// enemies that move faster. Changes are smoothed so one bad room doesn't flip everything around.
+
+// How strong the director's adjustments are by default, 0.0 turns them off and 2.0 doubles them.
+// The main menu changes it a step at a time, up to the max.
+pub const DIRECTOR_INTENSITY: f32 = 1.0;
+pub const DIRECTOR_INTENSITY_STEP: f32 = 0.25;
+pub const MAX_DIRECTOR_INTENSITY: f32 = 2.0;
+// How many cleared rooms it remembers
+pub const DIRECTOR_HISTORY: usize = 4;
+// How far pressure moves toward its new target after each room, 1.0 would jump straight there
+pub const DIRECTOR_SMOOTHING: f32 = 0.35;
+// What an "even" room looks like: damage taken (half hearts) and time to clear (ms)
+pub const DAMAGE_PAR: f32 = 2.0;
+pub const CLEAR_TIME_PAR: f32 = 20000.0;
+// At full pressure, the fraction of enemies removed or added
+pub const MAX_SPAWN_CHANGE: f32 = 0.5;
+// At full pressure, the fraction enemy speed is lowered or raised by
+pub const MAX_SPEED_CHANGE: f32 = 0.25;
+// Pressure at which ranged enemies lose their shots, like the old low hp rule
+pub const STRIP_RANGED_PRESSURE: f32 = 0.6;
+// How far (tiles) from the player enemies the director adds are put down
+pub const DIRECTOR_SPAWN_DISTANCE: i32 = 4;
+// At full pressure, the chance of an extra half heart when a room is cleared
+pub const MAX_DROP_CHANCE: f32 = 0.5;
+
+// How one cleared room went
+#[derive(Copy, Clone, Debug)]
+pub struct RoomReport {
+    pub damage_taken: i32,
+    pub clear_time: u128,
+    pub hp_left: i32,
+}
+
+pub struct Director {
+    pub enabled: bool, // Off for hard mode
+    pub intensity: f32,
+    // Above 0 the player is struggling and things get easier, below 0 they get harder. -1.0 to 1.0.
+    pub pressure: f32,
+    pub reports: VecDeque<RoomReport>,
+    // The room being fought in right now, None when it's already clear
+    room_start: Option<Instant>,
+    damage_taken: i32,
+    last_hp: i32,
+}
+
+impl Director {
+    pub fn new(intensity: f32, enabled: bool) -> Director {
+        Director {
+            enabled,
+            intensity,
+            pressure: 0.0,
+            reports: VecDeque::new(),
+            room_start: None,
+            damage_taken: 0,
+            last_hp: 0,
+        }
+    }
+
+    // Works out where pressure should be from the remembered rooms and moves it part way there
+    fn update_pressure(&mut self, max_hp: i32) {
+        if self.reports.is_empty() {
+            return;
+        }
+        let count = self.reports.len() as f32;
+        let avg_damage = self.reports.iter().map(|r| r.damage_taken as f32).sum::<f32>() / count;
+        let avg_time = self.reports.iter().map(|r| r.clear_time as f32).sum::<f32>() / count;
+
+        let damage = (avg_damage / DAMAGE_PAR - 1.0).clamp(-1.0, 1.0);
+        let time = (avg_time / CLEAR_TIME_PAR - 1.0).clamp(-1.0, 1.0);
+        // Positive when health has been going down over the remembered rooms
+        let first = self.reports.front().unwrap().hp_left;
+        let last = self.reports.back().unwrap().hp_left;
+        let trend = ((first - last) as f32 / max_hp.max(1) as f32 * 2.0).clamp(-1.0, 1.0);
+        // Being low on health matters most, half health counts as even
+        let low = (1.0 - last as f32 / max_hp.max(1) as f32 * 2.0).clamp(-1.0, 1.0);
+
+        let target = ((damage * 0.3 + time * 0.15 + trend * 0.2 + low * 0.35) * self.intensity).clamp(-1.0, 1.0);
+        self.pressure += (target - self.pressure) * DIRECTOR_SMOOTHING;
+    }
+
+    // Chance of an extra half heart when the current room is cleared
+    pub fn drop_chance(&self) -> f32 {
+        if !self.enabled {
+            return 0.0;
+        }
+        (self.pressure * MAX_DROP_CHANCE).max(0.0)
+    }
+}
+
+// Called when the player walks into a room, after their position has been set. Adjusts the room's
+// enemies the first time it's entered and starts timing it. Replaces Room::ease_enemy_difficulty.
+pub fn enter_room(game: & mut Game) {
+    let hp = game.player.hp;
+    let enabled = game.director.enabled;
+    let pressure = game.director.pressure;
+    let depth = game.cf;
+    let player_tile = tile_of(game.player.pos);
+    let room = game.current_room_mut();
+
+    let alive = room.enemies.iter().any(|e| !e.death);
+    if alive && !room.directed && enabled {
+        adjust_room(room, pressure, depth, player_tile);
+    }
+    room.directed = true;
+
+    game.director.room_start = if alive { Some(Instant::now()) } else { None };
+    game.director.damage_taken = 0;
+    game.director.last_hp = hp;
+}
+
+// Called every gameplay frame. Counts damage taken and notices when the room has been cleared.
+pub fn update(game: & mut Game) {
+    let hp = game.player.hp;
+    if hp < game.director.last_hp {
+        game.director.damage_taken += game.director.last_hp - hp;
+    }
+    game.director.last_hp = hp;
+
+    let start = match game.director.room_start {
+        Some( start ) => start,
+        None => return,
+    };
+    if BlackBoard::get_enemy_quantity(game) > 0 {
+        return;
+    }
+
+    // Room cleared
+    game.director.room_start = None;
+    let report = RoomReport {
+        damage_taken: game.director.damage_taken,
+        clear_time: start.elapsed().as_millis(),
+        hp_left: hp,
+    };
+    game.director.reports.push_back(report);
+    if game.director.reports.len() > DIRECTOR_HISTORY {
+        game.director.reports.pop_front();
+    }
+    let max_hp = game.player.max_hp();
+    game.director.update_pressure(max_hp);
+
+    // Clearing a room always pays out where the last enemy went down, the director decides if
+    // there's a little extra. Falls back to next to the player if it can't tell which was last.
+    let player_tile = game.player.current_frame_tile;
+    let tile = game.current_room().enemies.iter()
+        .filter(|e| e.death)
+        .max_by_key(|e| e.last_invincibility_time)
+        .map(|e| tile_of(e.pos))
+        .unwrap_or(player_tile);
+    let bonus = game.director.drop_chance();
+    drop_clear_reward(game.current_room_mut(), tile, player_tile, bonus);
+}
+
+// Thins out or fills up a room, and speeds up or slows down what's left
+fn adjust_room(room: & mut Room, pressure: f32, depth: usize, player_tile: Vec2<i32>) {
+    // The mini-boss is never touched, the trapdoor needs it beaten
+    let normal = room.enemies.iter().filter(|e| !e.death && !e.mini_boss && e.kind != EnemyKind::Final).count();
+    let change = (normal as f32 * pressure.abs() * MAX_SPAWN_CHANGE).round() as usize;
+
+    if pressure > 0.0 {
+        // Most dangerous ones go first
+        for _ in 0..change.min(normal.saturating_sub(1)) {
+            let worst = room.enemies.iter_with_handles()
+                .filter(|(_, e)| !e.death && !e.mini_boss && e.kind != EnemyKind::Final)
+                .max_by_key(|(_, e)| threat(e))
+                .map(|(handle, _)| handle);
+            match worst {
+                Some( handle ) => { room.enemies.remove(handle); }
+                None => break,
+            }
+        }
+    } else {
+        // Same kinds and elite chances as the floor's own enemies, on open ground away from the player
+        for _ in 0..change {
+            match room.open_tile_away_from(player_tile, DIRECTOR_SPAWN_DISTANCE) {
+                Some( tile ) => { room.enemies.insert(room_enemy(tile, depth)); }
+                None => break,
+            }
+        }
+    }
+
+    for enemy in room.enemies.iter_mut() {
+        if enemy.kind == EnemyKind::Final {
+            continue;
+        }
+        enemy.speed *= 1.0 - pressure * MAX_SPEED_CHANGE;
+        if pressure >= STRIP_RANGED_PRESSURE {
+            enemy.is_ranged = false;
+        }
+    }
+}
+
+// Rough idea of how dangerous an enemy is, for picking which to remove
+fn threat(enemy: &Enemy) -> i32 {
+    let mut score = enemy.attack_damage() + enemy.hp;
+    if enemy.is_ranged {
+        score += 2;
+    }
+    if enemy.elite.is_some() {
+        score += 3;
+    }
+    score
+}
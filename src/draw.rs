@@ -4,16 +4,32 @@ use crate::tile::*;
 use crate::menu::*;
 use crate::player::{BuffType};
 use crate::entity::*;
+use crate::enemydef::*;
+use crate::elite::*;
+use crate::miniboss::*;
+use crate::bosspattern::side_positions;
+use crate::perception::*;
 //use crate::finalenemy::*;
 use crate::blackboard::*;
+use crate::world::{Entity, Sprite};
 use roguelike::SDLCore;
 
 use std::time::Duration;
+use std::collections::HashMap;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::rect::Point;
 use sdl2::image::LoadTexture;
 use sdl2::render::TextureQuery;
+use sdl2::render::Texture;
+
+// Every texture one kind of enemy needs, loaded from its EnemyDef
+struct EnemyTextures<'a> {
+    idle: Texture<'a>,
+    ranged: Texture<'a>,
+    hit: Texture<'a>,
+    boss: Texture<'a>,
+}
 
 // Pseudo-code credits to Max Agoston in Computer Graphics and Geometric Modeling book, page 303
 pub fn hsv_to_rgb( h: f32, s: f32, v: f32 ) -> Color {
@@ -62,6 +78,64 @@ pub fn hsv_to_rgb( h: f32, s: f32, v: f32 ) -> Color {
     Color::RGBA( rgb.0 as u8, rgb.1 as u8, rgb.2 as u8, 255 )
 }
 
+// Where a (non boss) enemy's sprite goes, sized by sprite_size and standing on its walkbox
+fn enemy_sprite_rect(enemy: &Enemy, x_val: i32, y_val: i32) -> Rect {
+    let size = (enemy_def(enemy.kind).sprite_size as f32 * draw_scale(enemy)) as u32;
+    Rect::new(
+        enemy.get_pos_x() - (size/2) as i32 + 1 + x_val,
+        enemy.get_pos_y() - size as i32 + (enemy.box_es.get_walkbox(enemy.pos).height()/2) as i32 + y_val,
+        size, size)
+}
+
+// Loot lying on a tile at x, y. Hearts reuse the health bar's half heart sprite.
+fn draw_pickup(core: &mut SDLCore, heart: &Texture, sprite: SpriteID, x: i32, y: i32) -> Result<(), String> {
+    match sprite {
+        SpriteID::HalfHeart => {
+            core.wincan.copy(heart, None, Rect::new(x + 18, y + 8, 28, 48))?;
+        }
+        SpriteID::Heart => {
+            core.wincan.copy_ex(heart, None, Rect::new(x + 4, y + 8, 28, 48), 0.0, None, false, false)?;
+            core.wincan.copy_ex(heart, None, Rect::new(x + 32, y + 8, 28, 48), 0.0, None, true, false)?;
+        }
+        SpriteID::Coin => {
+            core.wincan.set_draw_color(Color::RGBA(255, 210, 60, 255));
+            core.wincan.fill_rect(Rect::new(x + 20, y + 20, 24, 24))?;
+            core.wincan.set_draw_color(Color::RGBA(150, 100, 20, 255));
+            core.wincan.draw_rect(Rect::new(x + 20, y + 20, 24, 24))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// Props don't have textures yet, crates are drawn as boxes that get darker as they're hit
+fn draw_prop(core: &mut SDLCore, prop: &Entity) -> Result<(), String> {
+    match prop.sprite {
+        Some( Sprite::Crate ) => {
+            let (hitbox, walkbox) = match (prop.get_hitbox(), prop.get_walkbox()) {
+                (Some( h ), Some( w )) => (h, w),
+                _ => return Ok(()),
+            };
+            let body = hitbox.union(walkbox);
+            let shade = match prop.health {
+                Some( h ) if h.hp < h.max_hp => 0.7,
+                _ => 1.0,
+            };
+            core.wincan.set_draw_color(Color::RGBA((160.0 * shade) as u8, (105.0 * shade) as u8, (50.0 * shade) as u8, 255));
+            core.wincan.fill_rect(body)?;
+            core.wincan.set_draw_color(Color::RGBA(90, 55, 20, 255));
+            core.wincan.draw_rect(body)?;
+            core.wincan.draw_line(body.top_left(), body.bottom_right())?;
+        }
+        Some( Sprite::Debris ) => {
+            core.wincan.set_draw_color(Color::RGBA(140, 90, 40, 255));
+            core.wincan.fill_rect(Rect::new(prop.pos.x as i32 - 3, prop.pos.y as i32 - 3, 6, 6))?;
+        }
+        None => {}
+    }
+    Ok(())
+}
+
 pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug: &bool, blackboard : &BlackBoard) -> Result<(), String> {
 
 // MOVE SOMEWHERE ELSE, TEXTURES SHOULD ONLY BE INITIALIZED ONCE
@@ -111,6 +185,16 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
 
             let main_menu = texture_creator.load_texture("assets/main_menu.png")?;
             core.wincan.copy(&main_menu, None, Rect::new(0, 0, WINDOW_WIDTH, WINDOW_HEIGHT))?;
+
+            // Difficulty director setting, changed with up and down
+            let intensity_surface = font.render( format!( "Director {:.2} (Up/Down)", game.director.intensity ).as_str() )
+                .blended( Color::RGBA( 255, 255, 255, 255 ) )
+                .map_err( |e| e.to_string() )?;
+            let intensity_tex = texture_creator.create_texture_from_surface( &intensity_surface )
+                .map_err( |e| e.to_string() )?;
+            let TextureQuery { width, height, .. } = intensity_tex.query();
+            let cx = ( WINDOW_WIDTH as i32 - (width / 2) as i32 ) / 2;
+            core.wincan.copy(&intensity_tex, None, Rect::new( cx, WINDOW_HEIGHT as i32 - 48, width / 2, height / 2 ))?;
         }
 
         MenuState::Victory => {
@@ -144,6 +228,13 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
             let slime_right_ch02 = texture_creator.load_texture("assets/slime_right_charge02.png")?;
             let slime_right_ch03 = texture_creator.load_texture("assets/slime_right_charge03.png")?;
 
+            let slime_up_ch01 = texture_creator.load_texture("assets/slime_up_charge01.png")?;
+            let slime_up_ch02 = texture_creator.load_texture("assets/slime_up_charge02.png")?;
+            let slime_up_ch03 = texture_creator.load_texture("assets/slime_up_charge03.png")?;
+            let slime_down_ch01 = texture_creator.load_texture("assets/slime_down_charge01.png")?;
+            let slime_down_ch02 = texture_creator.load_texture("assets/slime_down_charge02.png")?;
+            let slime_down_ch03 = texture_creator.load_texture("assets/slime_down_charge03.png")?;
+
             let slime_left_at01 = texture_creator.load_texture("assets/slime_left_attack01.png")?;
             let slime_left_at02 = texture_creator.load_texture("assets/slime_left_attack02.png")?;
             let slime_left_at03 = texture_creator.load_texture("assets/slime_left_attack03.png")?;
@@ -151,37 +242,40 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
             let slime_left_ch02 = texture_creator.load_texture("assets/slime_left_charge02.png")?;
             let slime_left_ch03 = texture_creator.load_texture("assets/slime_left_charge03.png")?;
 
-            /* enemy textures */
-            let speed_idle = texture_creator.load_texture("assets/speed_melee.png")?;
-            let attack_idle = texture_creator.load_texture("assets/attack_melee.png")?;
-            let health_idle = texture_creator.load_texture("assets/health-sprite-down.png")?;
-            
-            let speed_ranged = texture_creator.load_texture("assets/speed_idle.png")?;
-            let attack_ranged = texture_creator.load_texture("assets/wizard_attack_enemy.png")?;
-            let health_ranged = texture_creator.load_texture("assets/health_dragon_left.png")?;
-
-            let mut speed_hit = texture_creator.load_texture("assets/speed_idle_hit.png")?;
-            let mut attack_hit = texture_creator.load_texture("assets/wizard_attack_enemy_hit.png")?;
-            let mut health_hit = texture_creator.load_texture("assets/health-sprite-down_hit.png")?;
-            let mut boss_hit = texture_creator.load_texture( "assets/boss_hit.png" )?;
-
-            let health_atk = texture_creator.load_texture("assets/health-projectile.png")?;
-            let speed_atk = texture_creator.load_texture("assets/speed-projectile.png")?;
-            let attack_atk = texture_creator.load_texture("assets/attack-projectile.png")?;
+            /* enemy textures, the paths come from assets/enemies.txt */
+            let mut enemy_tex = Vec::new();
+            for (kind, _) in ENEMY_KINDS.iter() {
+                let def = enemy_def(*kind);
+                let (r, g, b) = def.tint;
+                let mut idle = texture_creator.load_texture(&def.sprite)?;
+                idle.set_color_mod(r, g, b);
+                let mut ranged = texture_creator.load_texture(&def.ranged_sprite)?;
+                ranged.set_color_mod(r, g, b);
+                enemy_tex.push(EnemyTextures {
+                    idle: idle,
+                    ranged: ranged,
+                    hit: texture_creator.load_texture(&def.hit_sprite)?,
+                    boss: texture_creator.load_texture(&def.boss_sprite)?,
+                });
+            }
+            // "!" when an enemy notices the player and "?" while it's searching
+            let noticed_surface = font.render( "!" ).blended( Color::RGBA( 255, 60, 60, 255 ) ).map_err( |e| e.to_string() )?;
+            let noticed_tex = texture_creator.create_texture_from_surface( &noticed_surface ).map_err( |e| e.to_string() )?;
+            let search_surface = font.render( "?" ).blended( Color::RGBA( 255, 230, 90, 255 ) ).map_err( |e| e.to_string() )?;
+            let search_tex = texture_creator.create_texture_from_surface( &search_surface ).map_err( |e| e.to_string() )?;
+
+            // Projectiles carry the path of their own sprite, each one is only loaded once
+            let mut projectile_tex: HashMap<&str, Texture> = HashMap::new();
+            for (kind, _) in ENEMY_KINDS.iter() {
+                let path = enemy_def(*kind).projectile_sprite.as_str();
+                if !projectile_tex.contains_key(path) {
+                    projectile_tex.insert(path, texture_creator.load_texture(path)?);
+                }
+            }
 
             //let hp_indicator = texture_creator.load_texture("assets/hp.png")?;
             //let hp_bomb_indicator = texture_creator.load_texture("assets/-3.png")?;
 
-            /*boss assets*/
-            let health_boss01 = texture_creator.load_texture("assets/boss_health01.png")?;
-            //let health_boss02 = texture_creator.load_texture("assets/boss_health02.png")?;
-
-            let speed_boss01 = texture_creator.load_texture("assets/boss_speed01.png")?;
-            //let speed_boss02 = texture_creator.load_texture("assets/boss_speed02.png")?;
-
-            let attack_boss01 = texture_creator.load_texture("assets/boss_attack01.png")?;
-            //let attack_boss02 = texture_creator.load_texture("assets/boss_attack02.png")?;
-
             //power assets
             let p_text = texture_creator.load_texture("assets/p_text.png")?;
             let p_text_health = texture_creator.load_texture("assets/p_text_health.png")?;
@@ -371,6 +465,11 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                     core.wincan.copy(&bomb_explosion, None, Rect::new(x_val, y_val, 64, 64))?;
                                 }
 
+                                SpriteID::HalfHeart | SpriteID::Heart | SpriteID::Coin => {
+                                    core.wincan.copy(&bricks, None, Rect::new(x_val, y_val, 64, 64))?;
+                                    draw_pickup(core, &pl_heart, t.sprite(), x_val, y_val)?;
+                                }
+
                                 SpriteID::TrapdoorLocked => {
                                     core.wincan.copy(&td_locked, None, Rect::new(x_val, y_val, 64, 64))?;
                                 }
@@ -470,6 +569,11 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                             core.wincan.copy(&bomb_explosion, None, Rect::new(x_val, y_val, 64, 64))?;
                         }
 
+                        SpriteID::HalfHeart | SpriteID::Heart | SpriteID::Coin => {
+                            core.wincan.copy(&bricks, None, Rect::new(x_val, y_val, 64, 64))?;
+                            draw_pickup(core, &pl_heart, t.sprite(), x_val, y_val)?;
+                        }
+
                         SpriteID::DoorLocked => {
 
                             if y < 3 {
@@ -578,9 +682,45 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
             if !(time_since_dmg <= Duration::from_millis(P_INVINCIBILITY_TIME - P_INVINCIBILITY_TIME/2) && time_since_dmg.as_millis()%100 < 50) &&
             !(time_since_dmg <= Duration::from_millis(P_INVINCIBILITY_TIME)&& time_since_dmg > Duration::from_millis(P_INVINCIBILITY_TIME - P_INVINCIBILITY_TIME/2) && time_since_dmg.as_millis()%200 < 100){
                 match game.player.get_dir() {
+                    // Holding a charge or letting one go
+                    dir if game.player.holding_charge() || (game.player.is_charging && game.player.recently_charged()) => {
+                        // Winding up while holding, then the long strike once it's let go
+                        let (right_tex, left_tex, up_tex, down_tex, reach) =
+                            if game.player.holding_charge() && game.player.charge_progress() < 0.4 {
+                                (&slime_right_at01, &slime_left_at01, &slime_up_at01, &slime_down_at01, 8)
+                            }
+                            else if game.player.holding_charge() {
+                                (&slime_right_ch01, &slime_left_ch01, &slime_up_ch01, &slime_down_ch01, 8)
+                            }
+                            else if time_since_attack.as_millis() < 50 {
+                                (&slime_right_ch02, &slime_left_ch02, &slime_up_ch02, &slime_down_ch02, 36)
+                            }
+                            else {
+                                (&slime_right_ch03, &slime_left_ch03, &slime_up_ch03, &slime_down_ch03, 124)
+                            };
+                        let top = game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val;
+                        match dir {
+                            Direction::Left => {
+                                core.wincan.copy(left_tex, None,
+                                    Rect::new(game.player.get_pos_x() - 35 + x_val - reach, top, (64 + reach) as u32, 64))?;
+                            }
+                            Direction::Right => {
+                                core.wincan.copy(right_tex, None,
+                                    Rect::new(game.player.get_pos_x() - 35 + x_val, top, (64 + reach) as u32, 64))?;
+                            }
+                            Direction::Up => {
+                                core.wincan.copy(up_tex, None,
+                                    Rect::new(game.player.get_pos_x() - 35 + x_val, top - reach, 64, (64 + reach) as u32))?;
+                            }
+                            Direction::Down => {
+                                core.wincan.copy(down_tex, None,
+                                    Rect::new(game.player.get_pos_x() - 35 + x_val, top, 64, (64 + reach) as u32))?;
+                            }
+                        }
+                    }
 
                     Direction::Up => {
-                        if game.player.recently_attacked() || game.player.recently_charged() {
+                        if game.player.recently_attacked() {
                             if time_since_attack.as_millis() % 250 < 50 && game.player.is_attacking{
                                 core.wincan.copy(&slime_up_at01, None,
                                     Rect::new(
@@ -624,7 +764,7 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                         }
                     }
                     Direction::Down => {
-                        if game.player.recently_attacked() || game.player.recently_charged() {
+                        if game.player.recently_attacked() {
                             if time_since_attack.as_millis() % 250 < 50 && game.player.is_attacking{
                             core.wincan.copy(&slime_down_at01, None,
                                 Rect::new(
@@ -668,8 +808,8 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                         }
                     }
                     Direction::Left => {
-                        if game.player.recently_attacked() || game.player.recently_charged() {
-                            if time_since_attack.as_millis() % 250 < 50 && game.player.is_attacking && !game.player.is_charging{
+                        if game.player.recently_attacked() {
+                            if time_since_attack.as_millis() % 250 < 50 && game.player.is_attacking{
                             core.wincan.copy(&slime_left_at01, None,
                                 Rect::new(
                                     game.player.get_pos_x() - 35 + x_val - 8,
@@ -677,7 +817,7 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                     64+8, 64)
                                 )?;
                             }
-                            else if time_since_attack.as_millis() % 250 < 100 && game.player.is_attacking && !game.player.is_charging{
+                            else if time_since_attack.as_millis() % 250 < 100 && game.player.is_attacking{
                             core.wincan.copy(&slime_left_at02, None,
                                 Rect::new(
                                     game.player.get_pos_x() - 35 + x_val - 36,
@@ -685,7 +825,7 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                     64+36, 64)
                                 )?;
                             }
-                            else if time_since_attack.as_millis() % 250  < 250 && game.player.is_attacking && !game.player.is_charging{
+                            else if time_since_attack.as_millis() % 250  < 250 && game.player.is_attacking{
                             core.wincan.copy(&slime_left_at03, None,
                                 Rect::new(
                                     game.player.get_pos_x() - 35 + x_val - 60,
@@ -693,38 +833,6 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                     64+60, 64)
                                 )?;
                             }
-                            else if time_since_attack.as_millis() % 1000 < 400 && game.player.is_charging{
-                            core.wincan.copy(&slime_left_at01, None,
-                                Rect::new(
-                                    game.player.get_pos_x() - 35 + x_val - 8,
-                                    game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                    64+8, 64)
-                                )?;
-                            }
-                            else if time_since_attack.as_millis() % 1000 < 800 && game.player.is_charging{
-                            core.wincan.copy(&slime_left_ch01, None,
-                                Rect::new(
-                                    game.player.get_pos_x() - 35 + x_val - 8,
-                                    game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                    64+8, 64)
-                                )?;
-                            }
-                            else if time_since_attack.as_millis() % 1000 < 850 && game.player.is_charging{
-                                core.wincan.copy(&slime_left_ch02, None,
-                                    Rect::new(
-                                        game.player.get_pos_x() - 35 + x_val - 36,
-                                        game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                        64+36, 64)
-                                    )?;
-                                }
-                            else if time_since_attack.as_millis() % 1000  < 1000 && game.player.is_charging{
-                                core.wincan.copy(&slime_left_ch03, None,
-                                    Rect::new(
-                                        game.player.get_pos_x() - 35 + x_val - 124,
-                                        game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                        64+124, 64)
-                                    )?;
-                                }
                             else {
                                 core.wincan.copy(&slime_left, None,
                                     Rect::new(
@@ -745,8 +853,8 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                         }
                     }
                     Direction::Right => {
-                        if game.player.recently_attacked() || game.player.recently_charged(){
-                            if time_since_attack.as_millis() % 250 < 50 && game.player.is_attacking && !game.player.is_charging{
+                        if game.player.recently_attacked(){
+                            if time_since_attack.as_millis() % 250 < 50 && game.player.is_attacking{
                             core.wincan.copy(&slime_right_at01, None,
                                 Rect::new(
                                     game.player.get_pos_x() - 35 + x_val,
@@ -754,7 +862,7 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                     64+8, 64)
                                 )?;
                             }
-                            else if time_since_attack.as_millis() % 250 < 100 && game.player.is_attacking && !game.player.is_charging{
+                            else if time_since_attack.as_millis() % 250 < 100 && game.player.is_attacking{
                             core.wincan.copy(&slime_right_at02, None,
                                 Rect::new(
                                     game.player.get_pos_x() - 35 + x_val,
@@ -762,7 +870,7 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                     64+36, 64)
                                 )?;
                             }
-                            else if time_since_attack.as_millis() % 250  < 250 && game.player.is_attacking && !game.player.is_charging{
+                            else if time_since_attack.as_millis() % 250  < 250 && game.player.is_attacking{
                             core.wincan.copy(&slime_right_at03, None,
                                 Rect::new(
                                     game.player.get_pos_x() - 35 + x_val,
@@ -770,38 +878,6 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                     64+60, 64)
                                 )?;
                             }
-                            else if time_since_attack.as_millis() % 1000 < 400 && game.player.is_charging{
-                            core.wincan.copy(&slime_right_at01, None,
-                                Rect::new(
-                                    game.player.get_pos_x() - 35 + x_val,
-                                    game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                    64+8, 64)
-                                )?;
-                            }
-                            else if time_since_attack.as_millis() % 1000 < 800 && game.player.is_charging{
-                            core.wincan.copy(&slime_right_ch01, None,
-                                Rect::new(
-                                    game.player.get_pos_x() - 35 + x_val,
-                                    game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                    64+8, 64)
-                                )?;
-                            }
-                            else if time_since_attack.as_millis() % 1000 < 850 && game.player.is_charging{
-                                core.wincan.copy(&slime_right_ch02, None,
-                                    Rect::new(
-                                        game.player.get_pos_x() - 35 + x_val,
-                                        game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                        64+36, 64)
-                                    )?;
-                                }
-                            else if  time_since_attack.as_millis() % 1000  < 1000 && game.player.is_charging{
-                                core.wincan.copy(&slime_right_ch03, None,
-                                    Rect::new(
-                                        game.player.get_pos_x() - 35 + x_val,
-                                        game.player.get_pos_y() - 64 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val,
-                                        64+124, 64)
-                                    )?;
-                                }
                             else {
                                 core.wincan.copy(&slime_right, None,
                                     Rect::new(
@@ -828,17 +904,48 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
             }
 
 
+            // Dust kicked up behind a dodge roll
+            if game.player.dodging() {
+                let feet_y = game.player.get_pos_y() + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val;
+                core.wincan.set_draw_color( Color::RGBA( 200, 190, 170, 255 ) );
+                for i in 1..4 {
+                    let back = (i * 14) as f32;
+                    core.wincan.fill_rect( Rect::new(
+                        (game.player.pos.x - game.player.dodge_dir.x * back) as i32 - 4 + x_val,
+                        (feet_y as f32 - game.player.dodge_dir.y * back) as i32 - 4,
+                        8 - i as u32 * 2, 8 - i as u32 * 2 ) )?;
+                }
+            }
+
+            // Charge meter over the player's head, turns white once it's full
+            if game.player.holding_charge() {
+                let progress = game.player.charge_progress();
+                let meter_x = game.player.get_pos_x() - 3 + x_val;
+                let meter_y = game.player.get_pos_y() - 64 - 12 + (game.player.box_es.get_walkbox(game.player.pos).height()/2) as i32 + y_val;
+                core.wincan.set_draw_color( Color::RGBA( 0, 0, 0, 255 ) );
+                core.wincan.fill_rect( Rect::new( meter_x - 24, meter_y, 48, 8 ) )?;
+                if progress >= 1.0 {
+                    core.wincan.set_draw_color( Color::RGBA( 255, 255, 255, 255 ) );
+                } else {
+                    core.wincan.set_draw_color( Color::RGBA( 255, 230, 90, 255 ) );
+                }
+                core.wincan.fill_rect( Rect::new( meter_x - 23, meter_y + 1, (46.0 * progress) as u32, 6 ) )?;
+            }
+
             //draw_enemies(textures);
 
+            // Crates and other component entities
+            for prop in game.current_room().props.entities.iter() {
+                draw_prop(core, prop)?;
+            }
+
             let enemies = &mut game.current_room_mut().enemies;
             for enemy in enemies.iter_mut()  {
 
                 for atk in &enemy.atk_list {
-                    let tex_a = match atk.kind {
-                        EnemyKind::Attack => &attack_atk,
-                        EnemyKind::Health => &health_atk,
-                        EnemyKind::Speed => &speed_atk,
-                        EnemyKind::Final => &health_atk, //Should not run
+                    let tex_a = match projectile_tex.get(atk.sprite) {
+                        Some( tex ) => tex,
+                        None => continue,
                     };
                     core.wincan.copy(&tex_a, None,
                         Rect::new(
@@ -848,28 +955,39 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                                 atk.box_es.hitbox.y)
                     )?;
                 }
+                // Projectiles that hit a rock or wall leave a short burst behind
+                for impact in &enemy.atk_impacts {
+                    let size = impact.box_es.hitbox.x.max(impact.box_es.hitbox.y) * 2;
+                    core.wincan.copy(&bomb_explosion, None,
+                        Rect::new(
+                                impact.pos.x as i32 - (size/2) as i32,
+                                impact.pos.y as i32 - (impact.box_es.hitbox.y/2) as i32 - (size/2) as i32,
+                                size,
+                                size)
+                    )?;
+                }
                 if !enemy.death() {
-                    let tex = match &enemy.kind {
-                        EnemyKind::Attack => {
-                            if enemy.is_ranged{&attack_ranged}
-                            else{&attack_idle}
-                        }
-                        EnemyKind::Health => {
-                            if enemy.is_ranged{&health_ranged}
-                            else{&health_idle}
-                        }
-                        EnemyKind::Speed => {
-                            if enemy.is_ranged{&speed_ranged}
-                            else{&speed_idle}
-                        }
-                        EnemyKind::Final => {
-                            match blackboard.boss_kind {
-                            EnemyKind::Attack => &attack_boss01,
-                            EnemyKind::Health =>  &health_boss01,
-                            EnemyKind::Speed => &speed_boss01,
-                            _ => &speed_idle
-                            } //TODO: sprite for final boss
+                    // Elites get an outline in their modifier's colour, drawn as a slightly bigger
+                    // silhouette behind the sprite
+                    match enemy.elite {
+                        Some( elite ) => {
+                            let (r, g, b) = modifier_color(elite.modifier);
+                            let outline = &mut enemy_tex[kind_index(enemy.kind)].hit;
+                            outline.set_color_mod( r, g, b );
+                            let rect = enemy_sprite_rect(enemy, x_val, y_val);
+                            core.wincan.copy( &outline, None, Rect::new( rect.x - 4, rect.y - 4, rect.width() + 8, rect.height() + 8 ) )?;
                         }
+                        None => {}
+                    }
+
+                    let textures = &enemy_tex[kind_index(enemy.kind)];
+                    let tex = if enemy.kind == EnemyKind::Final {
+                        // The boss takes after the kind of enemy the player has been killing
+                        &enemy_tex[kind_index(blackboard.boss_kind)].boss
+                    } else if enemy.is_ranged {
+                        &textures.ranged
+                    } else {
+                        &textures.idle
                     };
                     if  enemy.kind == EnemyKind::Final{
                         core.wincan.copy(&tex, None,
@@ -880,30 +998,53 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                         )?;
                     }
                     else {
-                        core.wincan.copy(&tex, None,
-                            Rect::new(
-                                enemy.get_pos_x() - 35 + 4 + x_val,
-                                enemy.get_pos_y() - 64 + (enemy.box_es.get_walkbox(enemy.pos).height()/2) as i32 + y_val,
-                                64, 64)
-                        )?;
+                        core.wincan.copy(&tex, None, enemy_sprite_rect(enemy, x_val, y_val))?;
+                    }
+
+                    // Winding up a dash, volley, summon or boss pattern. Flash white so the player sees it coming.
+                    if enemy.telegraph && enemy.state_timer.elapsed().as_millis() / 100 % 2 == 0 {
+                        let tex = &mut enemy_tex[kind_index(enemy.kind)].hit;
+                        tex.set_color_mod( 255, 255, 255 );
+                        if enemy.kind == EnemyKind::Final {
+                            let TextureQuery { width, height, .. } = tex.query();
+                            core.wincan.copy( &tex, None, Rect::new( enemy.get_pos_x() - (width * 2) as i32, enemy.get_pos_y() + 27 - (height * 4) as i32, width * 4, height * 4 ) )?;
+                        } else {
+                            core.wincan.copy( &tex, None, enemy_sprite_rect(enemy, x_val, y_val) )?;
+                        }
                     }
 
 
+                    // What the enemy knows about the player, drawn over its head
+                    match enemy.perception {
+                        Some( ref perception ) => {
+                            let rect = enemy_sprite_rect(enemy, x_val, y_val);
+                            let mid = rect.x + rect.width() as i32 / 2;
+                            let just_noticed = match perception.noticed_time {
+                                Some( time ) => time.elapsed().as_millis() < NOTICED_TIME,
+                                None => false,
+                            };
+                            if just_noticed || enemy.state == State::Search {
+                                let tex = if just_noticed { &noticed_tex } else { &search_tex };
+                                let TextureQuery { width, height, .. } = tex.query();
+                                core.wincan.copy( tex, None, Rect::new( mid - (width / 4) as i32, rect.y - (height / 2) as i32 - 4, width / 2, height / 2 ) )?;
+                            } else if !perception.aware && perception.alert > 0.0 {
+                                // Meter filling up while it's starting to notice something
+                                core.wincan.set_draw_color( Color::RGBA( 0, 0, 0, 255 ) );
+                                core.wincan.fill_rect( Rect::new( mid - 16, rect.y - 10, 32, 6 ) )?;
+                                core.wincan.set_draw_color( Color::RGBA( 255, 230, 90, 255 ) );
+                                core.wincan.fill_rect( Rect::new( mid - 15, rect.y - 9, (30.0 * perception.alert) as u32, 4 ) )?;
+                            }
+                        }
+                        None => {}
+                    }
+
                     // If the enemy was recently damaged..
                     if enemy.was_damaged() {
                         if enemy.last_invincibility_time.unwrap().elapsed() < Duration::from_millis( 500 ) {
-                            let enemy_rect = Rect::new(
-                                enemy.get_pos_x() - 35 + 4 + x_val,
-                                enemy.get_pos_y() - 64 + (enemy.box_es.get_walkbox(enemy.pos).height()/2) as i32 + y_val,
-                                64, 64);
+                            let enemy_rect = enemy_sprite_rect(enemy, x_val, y_val);
 
                             // Hit overlay
-                            let tex = match enemy.kind {
-                                EnemyKind::Speed => &mut speed_hit,
-                                EnemyKind::Health => &mut health_hit,
-                                EnemyKind::Attack => &mut attack_hit,
-                                EnemyKind::Final => &mut boss_hit,
-                            };
+                            let tex = &mut enemy_tex[kind_index(enemy.kind)].hit;
 
                             // The enemy is being healed here. Color modulate the texture
                             if enemy.last_damage_taken < 0 {
@@ -917,7 +1058,9 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                             if enemy.kind == EnemyKind::Final {
                                 core.wincan.copy( &tex, None, Rect::new( enemy.get_pos_x() - (width * 2) as i32, enemy.get_pos_y() + 27 - (height * 4) as i32, width * 4, height * 4 ) )?;
                             } else {
-                                core.wincan.copy( &tex, None, Rect::new( enemy_rect.x, enemy_rect.y, width * 4, height * 4 ) )?;
+                                // Hit sprites are 16px, scale them up to whatever size the enemy is drawn at
+                                let size = enemy_rect.width();
+                                core.wincan.copy( &tex, None, Rect::new( enemy_rect.x, enemy_rect.y, width * size / 16, height * size / 16 ) )?;
                             }
                         }
 
@@ -934,8 +1077,8 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                         let hp_percentage: f32 = enemy.hp as f32 / enemy.m_hp as f32;
 
                         // Determine healthbar color.
-                        // Speed enemies only have 2 hp, so it is okay for damage to immediately become yellow.
-                        let hp_color = if enemy.hp == enemy.m_hp - 1 && enemy.kind != EnemyKind::Speed {
+                        // Enemies with only 2 hp can go straight to yellow after one hit.
+                        let hp_color = if enemy.hp == enemy.m_hp - 1 && enemy.m_hp > 2 {
                             Color::RGBA( 0, 255, 0, 255 )
                         } else {
                             // Cool trick to use HSV to modulate color from green to red
@@ -956,6 +1099,30 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                 }
             }
 
+            // Mini-boss health bar along the bottom wall, shown for as long as it's alive
+            match game.current_room().enemies.iter().find(|e| e.mini_boss && !e.death) {
+                Some( boss ) => {
+                    let bar_x = LEFT_WALL + 4 * TILE_WIDTH;
+                    let bar_y = BOT_WALL - TILE_WIDTH + 22;
+                    let bar_w = (9 * TILE_WIDTH) as u32;
+
+                    let name_surface = font.render( "Guardian" )
+                                        .blended( Color::WHITE )
+                                        .map_err( |e| e.to_string() )?;
+                    let name_tex = texture_creator.create_texture_from_surface( &name_surface )
+                                    .map_err( |e| e.to_string() )?;
+                    let TextureQuery { width, height, .. } = name_tex.query();
+                    core.wincan.copy( &name_tex, None, Rect::new( bar_x - width as i32 - 12, bar_y + 10 - (height / 2) as i32, width, height ) )?;
+
+                    core.wincan.set_draw_color( Color::RGBA( 0, 0, 0, 255 ) );
+                    core.wincan.fill_rect( Rect::new( bar_x, bar_y, bar_w, 20 ) )?;
+                    let hp_percentage: f32 = boss.hp as f32 / boss.m_hp as f32;
+                    core.wincan.set_draw_color( hsv_to_rgb( 120.0 * hp_percentage, 1.0, 1.0 ) );
+                    core.wincan.fill_rect( Rect::new( bar_x + 3, bar_y + 3, ( (bar_w - 6) as f32 * hp_percentage ) as u32, 14 ) )?;
+                }
+                None => {}
+            }
+
             // If the player was attacked, show a quick damage indicator ("-1" in red)
             if game.player.was_attacked() {
                 let font_surface = font.render( format!( "-{}", game.player.last_damage_taken ).as_str() )
@@ -1078,6 +1245,17 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                 core.wincan.copy(&bomb_menu, None, Rect::new(16, 200, 64, 64))?;
             }
 
+            // Coins picked up so far
+            core.wincan.set_draw_color(Color::RGBA(255, 210, 60, 255));
+            core.wincan.fill_rect(Rect::new(24, 284, 20, 20))?;
+            let coin_surface = font.render( format!( "{}", game.player.coins ).as_str() )
+                .blended( Color::RGBA( 255, 255, 255, 255 ) )
+                .map_err( |e| e.to_string() )?;
+            let coin_tex = texture_creator.create_texture_from_surface( &coin_surface )
+                .map_err( |e| e.to_string() )?;
+            let TextureQuery { width: coin_w, height: coin_h, .. } = coin_tex.query();
+            core.wincan.copy(&coin_tex, None, Rect::new(56, 278, coin_w / 2, coin_h / 2))?;
+
             // Minimap (commented out first block as the block below does the same thing)
             // for x in 0..8 {
             //     for y in 0..8 {
@@ -1157,12 +1335,15 @@ pub fn base(game : &mut Game, core : &mut SDLCore, menu : &mut MenuState, &debug
                     }
 
                     // Final Boss debugging
-                    if !enemy.death() && enemy.kind == EnemyKind::Final {
-                        core.wincan.set_draw_color(Color::RGBA(255, 0, 0, 255));
-                        core.wincan.draw_rect(enemy.box_es.get_walkbox(enemy.pos))?;
-                        core.wincan.draw_rect(enemy.box_left_final.get_hitbox(enemy.box_left_final_pos))?;
-                        core.wincan.draw_rect(enemy.box_right_final.get_hitbox(enemy.box_right_final_pos))?;
-
+                    match enemy.boss {
+                        Some( boss ) if !enemy.death => {
+                            let (left, right) = side_positions(enemy);
+                            core.wincan.set_draw_color(Color::RGBA(255, 0, 0, 255));
+                            core.wincan.draw_rect(enemy.box_es.get_walkbox(enemy.pos))?;
+                            core.wincan.draw_rect(boss.side_box.get_hitbox(left))?;
+                            core.wincan.draw_rect(boss.side_box.get_hitbox(right))?;
+                        }
+                        _ => {}
                     }
 
                     for atk in &enemy.atk_list {
@@ -0,0 +1,154 @@
+use crate::util::*;
+use crate::entity::*;
+use crate::attack::*;
+use rand::Rng;
+use std::time::Instant;
+
+// Elite enemies. Any normal enemy can roll one modifier when a floor is generated, which changes how
+// it fights, gives it a coloured outline and makes it always drop its gem. Deeper floors roll more.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Modifier {
+    Armored, // Takes half damage (rounded up) and has extra health
+    Swift, // Moves faster
+    Regenerating, // Slowly heals back up
+    Explosive, // Bursts into a ring of shots when it dies
+    Shielded, // Blocks swings that come from the side it's facing
+}
+
+pub const MODIFIERS: [Modifier; 5] = [
+    Modifier::Armored,
+    Modifier::Swift,
+    Modifier::Regenerating,
+    Modifier::Explosive,
+    Modifier::Shielded,
+];
+
+// Enemy component for elites
+#[derive(Copy, Clone, Debug)]
+pub struct Elite {
+    pub modifier: Modifier,
+    pub last_regen: Instant, // Last time a regenerating elite healed
+}
+
+// Chance of an enemy being elite on the first floor, and how much it goes up each floor after
+pub const ELITE_BASE_CHANCE: f32 = 0.05;
+pub const ELITE_CHANCE_PER_FLOOR: f32 = 0.10;
+
+// The player's base swing only does 1 damage so halving alone wouldn't do anything
+pub const ARMORED_HP_BONUS: i32 = 2;
+pub const SWIFT_SPEED_MULT: f32 = 1.4;
+// Time (ms) between regenerating elites healing a half heart
+pub const REGEN_TIME: u128 = 3000;
+// The burst an explosive elite leaves behind
+pub const EXPLOSION_SHOTS: u32 = 8;
+pub const EXPLOSION_SPEED: f32 = 3.0;
+pub const EXPLOSION_DAMAGE: i32 = 1;
+pub const EXPLOSION_SIZE: u32 = 16;
+
+// Maybe turns a freshly spawned enemy into an elite. depth is the floor index, 0 for the first floor.
+pub fn roll_elite(enemy: & mut Enemy, depth: usize){
+    if enemy.kind == EnemyKind::Final {
+        return;
+    }
+    let mut rng = rand::thread_rng();
+    let chance = ELITE_BASE_CHANCE + ELITE_CHANCE_PER_FLOOR * depth as f32;
+    if rng.gen::<f32>() >= chance {
+        return;
+    }
+
+    make_elite(enemy, MODIFIERS[rng.gen_range(0..MODIFIERS.len())]);
+}
+
+// Gives an enemy a modifier, used by roll_elite and for mini-bosses
+pub fn make_elite(enemy: & mut Enemy, modifier: Modifier){
+    match modifier {
+        Modifier::Armored => {
+            enemy.m_hp += ARMORED_HP_BONUS;
+            enemy.hp = enemy.m_hp;
+        }
+        Modifier::Swift => {
+            enemy.speed *= SWIFT_SPEED_MULT;
+        }
+        _ => {}
+    }
+    enemy.elite = Some(Elite { modifier, last_regen: Instant::now() });
+}
+
+// Colour of an elite's outline
+pub fn modifier_color(modifier: Modifier) -> (u8, u8, u8) {
+    match modifier {
+        Modifier::Armored => (170, 170, 190),
+        Modifier::Swift => (90, 200, 255),
+        Modifier::Regenerating => (90, 255, 120),
+        Modifier::Explosive => (255, 110, 40),
+        Modifier::Shielded => (255, 225, 80),
+    }
+}
+
+// The enemy's modifier, if it's an elite
+pub fn modifier(enemy: &Enemy) -> Option<Modifier> {
+    enemy.elite.map(|elite| elite.modifier)
+}
+
+// Damage an elite actually takes from a hit of d. Heals (negative d) go through untouched.
+pub fn adjust_damage(enemy: &Enemy, d: i32) -> i32 {
+    if modifier(enemy) == Some(Modifier::Armored) && d > 0 {
+        return (d + 1) / 2;
+    }
+    d
+}
+
+// Called every frame before the enemy's behaviour runs
+pub fn tick(enemy: & mut Enemy){
+    if modifier(enemy) != Some(Modifier::Regenerating) || enemy.death {
+        return;
+    }
+    let full = enemy.hp >= enemy.m_hp;
+    let elite = match enemy.elite {
+        Some( ref mut elite ) => elite,
+        None => return,
+    };
+    if full {
+        elite.last_regen = Instant::now();
+        return;
+    }
+    if elite.last_regen.elapsed().as_millis() >= REGEN_TIME {
+        elite.last_regen = Instant::now();
+        enemy.hp = (enemy.hp + 1).min(enemy.m_hp);
+    }
+}
+
+// True if a shielded elite catches a swing from the player standing at from
+pub fn shield_blocks(enemy: &Enemy, from: Vec2<f32>) -> bool {
+    if modifier(enemy) != Some(Modifier::Shielded) {
+        return false;
+    }
+    let dx = from.x - enemy.pos.x;
+    let dy = from.y - enemy.pos.y;
+    match enemy.dir {
+        Direction::Up => dy < 0.0 && dy.abs() >= dx.abs(),
+        Direction::Down => dy > 0.0 && dy.abs() >= dx.abs(),
+        Direction::Left => dx < 0.0 && dx.abs() >= dy.abs(),
+        Direction::Right => dx > 0.0 && dx.abs() >= dy.abs(),
+    }
+}
+
+// Called once when an elite dies
+pub fn on_death(enemy: & mut Enemy){
+    if modifier(enemy) != Some(Modifier::Explosive) {
+        return;
+    }
+    // The shots stay in the dead enemy's atk_list, collision keeps moving them after it dies
+    for i in 0..EXPLOSION_SHOTS {
+        let angle = i as f32 / EXPLOSION_SHOTS as f32 * std::f32::consts::PI * 2.0;
+        let mut shot = AtkProjectile::new(enemy.pos, Vec2::new(angle.cos(), angle.sin()), &enemy.kind)
+            .with_behavior(ProjectileBehavior::Straight);
+        // Not every kind has a projectile of its own, so the burst brings its own stats
+        shot.speed = EXPLOSION_SPEED;
+        shot.damage = EXPLOSION_DAMAGE;
+        shot.box_es.hitbox = Vec2::new(EXPLOSION_SIZE, EXPLOSION_SIZE);
+        shot.box_es.walkbox = Vec2::new(EXPLOSION_SIZE, EXPLOSION_SIZE);
+        enemy.atk_list.push(shot);
+    }
+}
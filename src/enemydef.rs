@@ -0,0 +1,228 @@
+use crate::util::*;
+use crate::entity::*;
+use crate::attack::ProjectileBehavior;
+use std::fs;
+use std::sync::OnceLock;
+
+// Stats for every enemy kind, read from ENEMY_DEF_PATH when the game starts so balancing doesn't
+// need a recompile. See the top of that file for what each key means.
+
+pub const ENEMY_DEF_PATH: &str = "assets/enemies.txt";
+
+#[derive(Clone)]
+pub struct EnemyDef {
+    pub hp: i32,
+    pub speed: f32,
+    pub hitbox: Vec2<u32>,
+    pub walkbox: Vec2<u32>,
+    pub attackbox: Vec2<u32>,
+    pub damage: i32,
+    pub layer: MovementLayer,
+    pub ranged_chance: f32,
+    pub projectile_speed: f32,
+    pub projectile_damage: i32,
+    pub projectile_size: Vec2<u32>,
+    pub projectile_sprite: String,
+    pub projectile_behavior: ProjectileBehavior,
+    pub gem: Gem,
+    pub sprite: String,
+    pub ranged_sprite: String,
+    pub hit_sprite: String,
+    pub boss_sprite: String,
+    pub tint: (u8, u8, u8),
+    pub sprite_size: u32,
+    pub summons: Option<(EnemyKind, u32)>,
+    pub splits_into: Option<(EnemyKind, u32)>,
+    pub loot: Vec<(Loot, u32)>,
+}
+
+// Every kind the file has to define and the [section] it lives under
+pub const ENEMY_KINDS: [(EnemyKind, &str); 10] = [
+    (EnemyKind::Health, "health"),
+    (EnemyKind::Speed, "speed"),
+    (EnemyKind::Attack, "attack"),
+    (EnemyKind::Final, "final"),
+    (EnemyKind::Flying, "flying"),
+    (EnemyKind::Charger, "charger"),
+    (EnemyKind::Turret, "turret"),
+    (EnemyKind::Summoner, "summoner"),
+    (EnemyKind::Splitter, "splitter"),
+    (EnemyKind::Splitling, "splitling"),
+];
+
+static ENEMY_DEFS: OnceLock<Vec<EnemyDef>> = OnceLock::new();
+
+// Reads the definitions file. Has to run before any Enemy is made.
+pub fn load_enemy_defs(path: &str) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+    let defs = parse_enemy_defs(&text).map_err(|e| format!("{}: {}", path, e))?;
+    // Already loaded is fine, the first load wins
+    let _ = ENEMY_DEFS.set(defs);
+    Ok(())
+}
+
+pub fn enemy_def(kind: EnemyKind) -> &'static EnemyDef {
+    let defs = ENEMY_DEFS.get().expect("enemy definitions used before load_enemy_defs");
+    &defs[kind_index(kind)]
+}
+
+// Where a kind sits in ENEMY_KINDS, which is also the order defs are stored in
+pub fn kind_index(kind: EnemyKind) -> usize {
+    ENEMY_KINDS.iter().position(|k| k.0 == kind).unwrap()
+}
+
+fn parse_enemy_defs(text: &str) -> Result<Vec<EnemyDef>, String> {
+    // Split the file into (section name, key/value pairs)
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for (num, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            sections.push((line[1..line.len() - 1].trim().to_string(), Vec::new()));
+            continue;
+        }
+        let (key, value) = match line.find('=') {
+            Some( i ) => (line[..i].trim(), line[i + 1..].trim()),
+            None => return Err(format!("line {}: expected key = value", num + 1)),
+        };
+        match sections.last_mut() {
+            Some( section ) => section.1.push((key.to_string(), value.to_string())),
+            None => return Err(format!("line {}: {} is outside of a [section]", num + 1, key)),
+        }
+    }
+
+    let mut defs = Vec::new();
+    for (_, name) in ENEMY_KINDS.iter() {
+        let values = match sections.iter().find(|s| s.0 == *name) {
+            Some( section ) => &section.1,
+            None => return Err(format!("missing [{}]", name)),
+        };
+        defs.push(parse_def(name, values)?);
+    }
+    Ok(defs)
+}
+
+fn parse_def(name: &str, values: &[(String, String)]) -> Result<EnemyDef, String> {
+    let get = |key: &str| -> Result<&str, String> {
+        match values.iter().find(|v| v.0 == key) {
+            Some( v ) => Ok(v.1.as_str()),
+            None => Err(format!("[{}] is missing {}", name, key)),
+        }
+    };
+    let bad = |key: &str| format!("[{}] has a bad value for {}", name, key);
+
+    let int = |key: &str| -> Result<i32, String> { get(key)?.parse::<i32>().map_err(|_| bad(key)) };
+    let float = |key: &str| -> Result<f32, String> { get(key)?.parse::<f32>().map_err(|_| bad(key)) };
+    let size = |key: &str| -> Result<Vec2<u32>, String> {
+        let parts: Vec<&str> = get(key)?.split_whitespace().collect();
+        if parts.len() != 2 {
+            return Err(bad(key));
+        }
+        let w = parts[0].parse::<u32>().map_err(|_| bad(key))?;
+        let h = parts[1].parse::<u32>().map_err(|_| bad(key))?;
+        Ok(Vec2::new(w, h))
+    };
+
+    let layer = match get("layer")? {
+        "walker" => MovementLayer::Walker,
+        "flyer" => MovementLayer::Flyer,
+        "phaser" => MovementLayer::Phaser,
+        _ => return Err(bad("layer")),
+    };
+    let gem = match get("gem")? {
+        "red" => Gem::Red,
+        "blue" => Gem::Blue,
+        "yellow" => Gem::Yellow,
+        "none" => Gem::None,
+        _ => return Err(bad("gem")),
+    };
+    // "<kind> <count>" or "none"
+    let spawn = |key: &str| -> Result<Option<(EnemyKind, u32)>, String> {
+        let parts: Vec<&str> = get(key)?.split_whitespace().collect();
+        if parts.len() == 1 && parts[0] == "none" {
+            return Ok(None);
+        }
+        if parts.len() != 2 {
+            return Err(bad(key));
+        }
+        let kind = match ENEMY_KINDS.iter().find(|k| k.1 == parts[0]) {
+            Some( k ) => k.0,
+            None => return Err(bad(key)),
+        };
+        let count = parts[1].parse::<u32>().map_err(|_| bad(key))?;
+        Ok(Some((kind, count)))
+    };
+
+    // "<name> <args...>", see the top of the file
+    let behavior = {
+        let parts: Vec<&str> = get("projectile_behavior")?.split_whitespace().collect();
+        let arg = |i: usize| -> Result<f32, String> {
+            match parts.get(i) {
+                Some( v ) => v.parse::<f32>().map_err(|_| bad("projectile_behavior")),
+                None => Err(bad("projectile_behavior")),
+            }
+        };
+        match parts.first() {
+            Some( &"straight" ) => ProjectileBehavior::Straight,
+            Some( &"homing" ) => ProjectileBehavior::Homing(arg(1)?),
+            Some( &"accelerating" ) => ProjectileBehavior::Accelerating(arg(1)?, arg(2)?),
+            Some( &"sine" ) => ProjectileBehavior::Sine(arg(1)?, arg(2)?),
+            Some( &"bouncing" ) => ProjectileBehavior::Bouncing(arg(1)? as u32),
+            Some( &"splitting" ) => ProjectileBehavior::Splitting(arg(1)? as u32),
+            Some( &"puddle" ) => ProjectileBehavior::Puddle(arg(1)? as u128),
+            _ => return Err(bad("projectile_behavior")),
+        }
+    };
+
+    // "<loot> <weight>, <loot> <weight>, ..."
+    let mut loot = Vec::new();
+    for entry in get("loot")?.split(',') {
+        let parts: Vec<&str> = entry.split_whitespace().collect();
+        if parts.len() != 2 {
+            return Err(bad("loot"));
+        }
+        let item = match parts[0] {
+            "nothing" => Loot::Nothing,
+            "half_heart" => Loot::HalfHeart,
+            "heart" => Loot::Heart,
+            "bomb" => Loot::Bomb,
+            "coin" => Loot::Coin,
+            _ => return Err(bad("loot")),
+        };
+        let weight = parts[1].parse::<u32>().map_err(|_| bad("loot"))?;
+        loot.push((item, weight));
+    }
+
+    let tint: Vec<u8> = get("tint")?.split_whitespace().filter_map(|c| c.parse::<u8>().ok()).collect();
+    if tint.len() != 3 {
+        return Err(bad("tint"));
+    }
+
+    Ok(EnemyDef {
+        hp: int("hp")?,
+        speed: float("speed")?,
+        hitbox: size("hitbox")?,
+        walkbox: size("walkbox")?,
+        attackbox: size("attackbox")?,
+        damage: int("damage")?,
+        layer,
+        ranged_chance: float("ranged_chance")?,
+        projectile_speed: float("projectile_speed")?,
+        projectile_damage: int("projectile_damage")?,
+        projectile_size: size("projectile_size")?,
+        projectile_sprite: get("projectile_sprite")?.to_string(),
+        projectile_behavior: behavior,
+        gem,
+        sprite: get("sprite")?.to_string(),
+        ranged_sprite: get("ranged_sprite")?.to_string(),
+        hit_sprite: get("hit_sprite")?.to_string(),
+        boss_sprite: get("boss_sprite")?.to_string(),
+        tint: (tint[0], tint[1], tint[2]),
+        sprite_size: get("sprite_size")?.parse::<u32>().map_err(|_| bad("sprite_size"))?,
+        summons: spawn("summons")?,
+        splits_into: spawn("splits_into")?,
+        loot,
+    })
+}
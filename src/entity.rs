@@ -3,13 +3,24 @@ use crate::attack::*;
 use crate::util::*;
 use std::time::{Duration, Instant};
 use crate::boxes::*;
+use crate::arena::Handle;
+use crate::knockback;
 //use crate::yellowenemy::*;
 //use crate::finalenemy::*;
 use crate::blackboard::*;
 //use crate::tile::*;
-use std::collections::VecDeque;
-use crate::room::ROOM_HEIGHT;
-use crate::room::ROOM_WIDTH;
+use crate::room::Room;
+use crate::navigation::*;
+use crate::behavior::*;
+use crate::enemydef::*;
+use crate::elite::*;
+use crate::bosspattern::*;
+use crate::perception::{notice, Perception};
+use crate::knockback::Knockback;
+use crate::chargerenemy::ChargerState;
+use crate::turretenemy::TurretState;
+use crate::tile::Walkability;
+use crate::collision::MAX_SUBSTEP;
 //use crate::room::*;
 
 use rand::Rng;
@@ -36,9 +47,46 @@ pub enum EnemyKind {
     Attack,
     Health,
     Speed,
+    Flying,
+    Charger,
+    Turret,
+    Summoner,
+    Splitter,
+    Splitling,
     Final
 }
 
+// Which tiles an enemy can move over. Pathfinding and tile collision both go off this.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MovementLayer {
+    Walker, // Stopped by pits, rocks and walls
+    Flyer,  // Flies over pits and rocks, only walls stop it
+    Phaser, // No tile stops it, but it still paths like a walker instead of beelining through rocks
+}
+
+impl MovementLayer {
+    pub fn blocked_by(&self, walkability: &Walkability) -> bool {
+        match self {
+            MovementLayer::Walker => {
+                match walkability {
+                    Walkability::Wall | Walkability::Rock | Walkability::Pit => true,
+                    _ => false,
+                }
+            }
+            MovementLayer::Flyer => *walkability == Walkability::Wall,
+            MovementLayer::Phaser => false,
+        }
+    }
+
+    // Layer used to plan a route, phasers take the walking route when there is one
+    pub fn nav_layer(&self) -> MovementLayer {
+        match self {
+            MovementLayer::Phaser => MovementLayer::Walker,
+            _ => *self,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum State{
     Attack,
@@ -47,6 +95,7 @@ pub enum State{
     Chase,
     Heal,
     Idle,
+    Search, // Lost the player, checking where they were last seen or heard
 }
 
 
@@ -62,9 +111,12 @@ pub struct Enemy {
     pub movement_vec: Vec2<f32>,
     pub last_dir_update: Option< Instant >,
     pub kind: EnemyKind,
+    pub layer: MovementLayer,
+    pub behavior: &'static dyn EnemyBehavior,
     pub death: bool,
     pub power: bool,
     pub atk_list: Vec<AtkProjectile>,
+    pub atk_impacts: Vec<AtkImpact>,
     pub last_invincibility_time: Option<Instant>,
     pub state: State,
     pub is_attacking: bool,
@@ -73,18 +125,26 @@ pub struct Enemy {
     pub is_healing: bool,
     pub last_damage_taken: i32,
     pub is_ranged: bool,
+    pub cover_tile: Option<Vec2<i32>>,
+    pub telegraph: bool, // Winding up something the player should see coming, draw flashes the sprite
+    pub final_enemies_to_spawn: Vec<Enemy>, // Minions and split-offs waiting for main.rs to add them to the room
+    pub mini_boss: bool, // Guards the trapdoor, see miniboss.rs
+    pub loot_dropped: bool, // Loot is rolled once, on the frame it dies
+
+    // Components only some kinds have, the same idea as the optional components in world.rs.
+    // Enemy::new gives each kind the ones it uses.
+    pub charger: Option<ChargerState>, // Chargers, see chargerenemy.rs
+    pub turret: Option<TurretState>, // Turrets, see turretenemy.rs
+    pub boss: Option<BossState>, // The boss, see bosspattern.rs
+    pub minions: Option<Vec<Handle>>, // Summoners, what they summoned that's still alive
+    pub elite: Option<Elite>, // Set by roll_elite when the floor is generated
+    pub perception: Option<Perception>, // Kinds that have to notice the player, see perception.rs
+    pub knockback: Option<Knockback>, // Kinds that can be shoved and stunned, see knockback.rs
 
     pub time_scale: f32,
 
-    //BOSS ONLY
-    pub box_left_final: Box,
-    pub box_left_final_pos: Vec2<f32>,
-    pub box_right_final: Box,
-    pub box_right_final_pos: Vec2<f32>,
-    pub final_enemies_to_spawn: Vec<Enemy>,
     pub last_shot_time: Option<Instant>,
     pub is_shooting: bool,
-    pub g_kind: EnemyKind,
     pub state_timer: Instant,
 
 
@@ -94,9 +154,24 @@ impl Health for Enemy {
     fn max_hp(&self) -> i32 { self.m_hp }
     fn health(&self) -> i32 { self.hp }
     fn damage(&mut self, d: i32) -> i32 {
+        // Boss shrugs off hits while it's changing phase
+        if boss_invulnerable(self) && d > 0 {
+            return self.hp;
+        }
+        let d = adjust_damage(self, d);
+        // Getting hit gives the player away
+        if d > 0 {
+            notice(self);
+        }
         self.last_damage_taken = d;
+        let was_dead = self.death;
         self.hp = (self.hp - d).max(P_DEATH_HP);
+        check_phase(self);
         self.death();
+        if self.death && !was_dead {
+            self.queue_split();
+            on_death(self);
+        }
         self.hp
     }
 
@@ -120,19 +195,35 @@ impl Enemy {
             lastpos: Vec2::new(-1.0, 0.0),
             //box_es: Box::new(Vec2::new(40, 30), Vec2::new(40, 40), Vec2::new(40, 30)),
             box_es: box_kind(kind),
-            speed: speed_kind(kind),
+            speed: enemy_def(kind).speed,
             dir: Direction::Right,
-            hp: health_kind(kind),
-            m_hp: health_kind(kind),
+            hp: enemy_def(kind).hp,
+            m_hp: enemy_def(kind).hp,
             movement_vec: Vec2::new(-1.0, 0.0),
             last_dir_update: None,
             kind: kind,
+            layer: enemy_def(kind).layer,
+            behavior: behavior_kind(kind),
             death: false,
             power: false,
             atk_list: Vec::new(),
+            atk_impacts: Vec::new(),
             state: State::Idle,
             last_damage_taken: 0,
-            is_ranged: set_ranged(),
+            is_ranged: set_ranged(kind),
+            cover_tile: None,
+            telegraph: false,
+            final_enemies_to_spawn: Vec::<Enemy>::new(),
+            mini_boss: false,
+            loot_dropped: false,
+            charger: if kind == EnemyKind::Charger { Some(ChargerState::new()) } else { None },
+            turret: if kind == EnemyKind::Turret { Some(TurretState::new()) } else { None },
+            boss: if kind == EnemyKind::Final { Some(BossState::new()) } else { None },
+            minions: if kind == EnemyKind::Summoner { Some(Vec::new()) } else { None },
+            elite: None,
+            perception: if behavior_kind(kind).perceives() { Some(Perception::new()) } else { None },
+            // The boss doesn't move at all
+            knockback: if kind == EnemyKind::Final { None } else { Some(Knockback::new()) },
 
             current_frame_tile: Vec2::new(0,0),
             last_invincibility_time: None,
@@ -145,58 +236,74 @@ impl Enemy {
 
             time_scale: 1.0,
 
-            //FINAL BOSS ONLY
-            box_left_final: Box::new(Vec2::new(30, 70), Vec2::new(0, 0), Vec2::new(0, 0)),
-            box_left_final_pos: Vec2::new(position.x - 60.0, position.y),
-            box_right_final: Box::new(Vec2::new(30, 70), Vec2::new(0, 0), Vec2::new(0, 0)),
-            box_right_final_pos: Vec2::new(position.x + 60.0, position.y),
-            final_enemies_to_spawn: Vec::<Enemy>::new(),
             last_shot_time: None,
             is_shooting: false,
-            g_kind: EnemyKind::Attack,
             state_timer: Instant::now(),
 
         }
     }
 
     pub fn update(& mut self, blackboard: &BlackBoard) {
+        // Where this frame's move started, collision sweeps from here
+        self.lastpos = self.pos;
         self.current_frame_tile = Vec2::new(
             (self.get_pos_x() - LEFT_WALL) / TILE_WIDTH,
             (self.get_pos_y() - TOP_WALL) / TILE_WIDTH
         );
         //self.update_dir(blackboard.player_frame_tile);
         //println!("{:?}", self.current_frame_tile);
-        match self.kind {
-            EnemyKind::Health => {
-                crate::redenemy::update(self, blackboard)
-            }
-            EnemyKind::Speed => {
-                crate::blueenemy::update(self, blackboard)
-            }
-            EnemyKind::Attack => {
-                crate::yellowenemy::update(self, blackboard);
-            }
-            EnemyKind::Final => {
-                crate::finalenemy::update(self, blackboard);
-            }
+        tick(self);
+        // Still sliding from a hit, and too stunned to think until it wears off
+        match self.knockback {
+            Some( ref mut knockback ) => knockback::step(&mut knockback.velocity, &mut self.pos, self.time_scale),
+            None => {}
         }
+        if knockback::stunned(self) {
+            return;
+        }
+        self.behavior.update(self, blackboard);
     }
 
 
-    //BOSS ONLY
     pub fn add_enemies(&mut self, enemy: Enemy){
         self.final_enemies_to_spawn.push(enemy);
   }
 
-    pub fn attack_damage(&self) -> i32 {
-        use EnemyKind::*;
-        match self.kind {
-            Health => { 1 }
-            Speed  => { 1 }
-            Attack => { 2 }
-            Final =>  { 1 }
+    // Queues count enemies of a kind in a ring around us, main.rs adds them to the room next frame
+    pub fn queue_spawns(&mut self, kind: EnemyKind, count: u32, radius: f32) {
+        for i in 0..count {
+            let angle = i as f32 / count as f32 * std::f32::consts::PI * 2.0;
+            let pos = Vec2::new(self.pos.x + angle.cos() * radius, self.pos.y + angle.sin() * radius);
+            let mut new_enemy = Enemy::new(pos, kind);
+            // Minions and split-offs already know where the player is
+            notice(&mut new_enemy);
+            new_enemy.state = State::Chase;
+            self.final_enemies_to_spawn.push(new_enemy);
         }
-  }
+    }
+
+    // Spawns main.rs still has to add for us
+    pub fn has_pending_spawns(&self) -> bool {
+        !self.final_enemies_to_spawn.is_empty()
+    }
+
+    // Splitters break apart when they die
+    pub fn queue_split(&mut self) {
+        match enemy_def(self.kind).splits_into {
+            Some( (kind, count) ) => {
+                self.queue_spawns(kind, count, 20.0);
+                // So the swing that killed the parent doesn't take out the pieces too
+                for piece in self.final_enemies_to_spawn.iter_mut() {
+                    piece.update_invincibility_time();
+                }
+            }
+            None => {}
+        }
+    }
+
+    pub fn attack_damage(&self) -> i32 {
+        enemy_def(self.kind).damage
+    }
 
     /*pub fn switch_heal(&mut self) {
         if self.is_healing {
@@ -206,96 +313,89 @@ impl Enemy {
         }
     }*/
 
+    // Points movement_vec at the next stop on the way to target. Heading for the player uses the
+    // blackboard's shared flow field for our layer, anything else runs A*. Doesn't move the enemy itself.
     pub fn pathfinding(&mut self, target: Vec2<f32>, blackboard: &BlackBoard){
 
-        let target_tile = Vec2::new(
-            (target.x as i32 - LEFT_WALL) / TILE_WIDTH,
-            (target.y as i32 - TOP_WALL) / TILE_WIDTH
-        ); //The target tile
-
-        let none = Vec2::new(-1, -1);
-
+        let target_tile = tile_of(target); //The target tile
         let start_tile = self.current_frame_tile;
-        let mut cur_tile = start_tile; //The current tile
-
-        // Don't do additional code if already in target tile
-        if start_tile == target_tile { return; }
 
+        // Same tile, nothing to path around so head straight there
+        if start_tile == target_tile || blackboard.cr_tiles.is_empty() {
+            self.steer_towards(target);
+            return;
+        }
 
-        let mut queue: VecDeque<Vec2<i32>> = VecDeque::new(); //The queue of tiles to be checked
-        //let mut visited:Vec<Vec2<i32>> = Vec::new(); //Tiles that have been visited
-        let mut seen:Vec<Vec2<i32>> = Vec::new(); //Tiles that have been seen
-        let mut parent_array:Vec<Vec<Vec2<i32>>> = Vec::new(); //Parent array ()
-        let mut neighbors:Vec<Vec2<i32>> = Vec::new();
-        neighbors.resize(4,none);
-        parent_array.resize(ROOM_WIDTH as usize, Vec::new());
-
+        let layer = self.layer.nav_layer();
+        let passable = |tile: Vec2<i32>| blackboard.is_passable(tile, layer);
+        let field = blackboard.flow_field_for(layer);
+        let path = if target_tile == field.target {
+            field.path_from(start_tile, PATH_LOOKAHEAD, &passable)
+        } else {
+            astar(start_tile, target_tile, blackboard.nav_settings, &passable).unwrap_or_default()
+        };
+
+        // Aim for the furthest tile we can walk straight to instead of zig-zagging tile by tile
+        let mut waypoint = None;
+        for tile in path.iter().take(PATH_LOOKAHEAD) {
+            let point = if *tile == target_tile { target } else { BlackBoard::tile_center(*tile) };
+            if waypoint.is_none() || line_clear(self.pos, point, &|t| !passable(t)) {
+                waypoint = Some(point);
+            } else {
+                break;
+            }
+        }
 
-        for i in 0..ROOM_WIDTH{
-            parent_array[i as usize].resize(ROOM_HEIGHT as usize, none);
+        match waypoint {
+            Some( point ) => self.steer_towards(point),
+            None => {}
         }
-        //visited.push(cur_tile);
-        queue.push_back(cur_tile);
-        seen.push(cur_tile);
+    }
 
+    // Sets movement_vec (and facing) to point at a position
+    pub fn steer_towards(&mut self, point: Vec2<f32>) {
+        let vector = Vec2::new(point.x - self.pos.x, point.y - self.pos.y);
+        let length = (vector.x * vector.x + vector.y * vector.y).sqrt();
+        if length < 1.0 {
+            return;
+        }
+        self.movement_vec = Vec2::new(vector.x / length, vector.y / length);
+        self.dir = dir_from_vec(self.movement_vec, self.dir);
+    }
 
-//        let right_tile = Vec2::new(cur_tile.x+1, cur_tile.y);
-//        let left_tile = Vec2::new(cur_tile.x-1, cur_tile.y);
-//        let up_tile = Vec2::new(cur_tile.x, cur_tile.y-1);
-//        let down_tile = Vec2::new(cur_tile.x, cur_tile.y+1);
-//
-//
-//        neighbors.push(right_tile); //neighbors[0] = right_tile
-//        neighbors.push(left_tile); //neighbors[1] = left_tile
-//        neighbors.push(up_tile); //neighbors[2] = up_tile
-//        neighbors.push(down_tile); //neighbors[3] = down_tile
-        /*
-        for tile in neighbors.iter() {
-            let real_tile = *tile;
+    // Pathfinds towards target and moves one step
+    pub fn move_towards(&mut self, target: Vec2<f32>, blackboard: &BlackBoard) {
+        // Close enough, don't jitter back and forth over the target
+        let vector = Vec2::new(target.x - self.pos.x, target.y - self.pos.y);
+        if (vector.x * vector.x + vector.y * vector.y).sqrt() <= self.speed {
+            return;
+        }
 
-            parent_array[real_tile.x as usize][real_tile.y as usize] = cur_tile;
+        self.pathfinding(target, blackboard);
+        self.pos.x += self.movement_vec.x * self.speed * self.time_scale;
+        self.pos.y += self.movement_vec.y * self.speed * self.time_scale;
+    }
 
-            seen.push(real_tile);
-            queue.push_back(real_tile);
-        }
-        */
-        while !queue.is_empty() {
-            cur_tile = queue.pop_front().unwrap();
-            if cur_tile == target_tile {
-                break;
-            }
-            //visited.push(cur_tile);
-
-            neighbors[0] = Vec2::new(cur_tile.x+1, cur_tile.y); //add right neighbor
-            neighbors[1] = Vec2::new(cur_tile.x-1, cur_tile.y); //add left neighbor
-            neighbors[2] = Vec2::new(cur_tile.x, cur_tile.y-1); //add up neighbor
-            neighbors[3] = Vec2::new(cur_tile.x, cur_tile.y+1); //add down neighbor
-
-            for tile in neighbors.iter() {
-                let real_tile = *tile;
-
-                if(real_tile.x >= 0 && real_tile.x < ROOM_WIDTH) &&  //The tile x is within the room width
-                (real_tile.y >= 0 && real_tile.y < ROOM_HEIGHT) &&  //The tile y is within the room height
-                blackboard.is_walkable(real_tile) &&
-                !seen.iter().any(|&i| i==real_tile) //The tile has not been seen yet
-                {
-                    parent_array[real_tile.x as usize][real_tile.y as usize] = cur_tile;
-                    seen.push(real_tile);
-                    queue.push_back(real_tile);
+    // Heads for the nearest tile the player can't see and waits there.
+    // Returns false if there is no cover nearby so the caller can fall back to running away.
+    pub fn seek_cover(&mut self, blackboard: &BlackBoard) -> bool {
+        // The player moved and can see our hiding spot now, find a new one
+        let needs_new = match self.cover_tile {
+            Some( tile ) => blackboard.player_in_sight(BlackBoard::tile_center(tile)),
+            None => true,
+        };
+        if needs_new {
+            self.cover_tile = blackboard.find_cover(self.current_frame_tile, COVER_SEARCH_RADIUS, self.layer.nav_layer());
+        }
+
+        match self.cover_tile {
+            Some( tile ) => {
+                if self.current_frame_tile != tile || !Enemy::in_cover(self, blackboard) {
+                    self.move_towards(BlackBoard::tile_center(tile), blackboard);
                 }
+                true
             }
-        }
-
-        let mut path:Vec<Vec2<i32>> = Vec::new();
-        while cur_tile!=start_tile{
-            path.push(cur_tile);
-            cur_tile = parent_array[cur_tile.x as usize][cur_tile.y as usize];
-        }
-
-        //path.push(start_tile);
-        match path.pop() {
-            Some( next ) => self.update_dir( next ),
-            None => {}
+            None => false
         }
     }
 
@@ -324,6 +424,17 @@ impl Enemy {
     }
 
 
+    // take_damage that also knocks the enemy back from from and stuns it. Returns true if the hit landed.
+    pub fn take_hit(&mut self, amount: i32, cooldown_window_ms: u64, from: Vec2<f32>, force: f32) -> bool {
+        let before = self.last_invincibility_time;
+        self.take_damage(amount, cooldown_window_ms);
+        if self.last_invincibility_time == before {
+            return false;
+        }
+        knockback::knock_enemy(self, from, force);
+        true
+    }
+
     pub fn was_damaged(&self) -> bool {
         match self.last_invincibility_time {
             Some( time ) => time.elapsed() < Duration::from_secs( 1 ),
@@ -332,35 +443,6 @@ impl Enemy {
     }
 
 
-    //Old update direction without pathfinding
-    pub fn update_dir(& mut self, frame_tile: Vec2<i32>){
-        let e_x = self.current_frame_tile.x;
-        let e_y = self.current_frame_tile.y;
-
-        let p_x = frame_tile.x;
-        let p_y = frame_tile.y;
-        if e_x == p_x && e_y < p_y
-        {
-            self.dir = Direction::Down;
-        }
-
-        if e_x == p_x && e_y > p_y
-
-        {
-            self.dir = Direction::Up;
-        }
-
-        if e_x > p_x
-        {
-            self.dir = Direction::Left;
-        }
-
-        if e_x < p_x
-        {
-            self.dir = Direction::Right;
-        }
-    }
-
     pub fn distance_to_player(enemy: & mut Enemy, blackboard: &BlackBoard) -> f64 {
         let vector = Vec2::new(blackboard.playerpos.x - enemy.pos.x, blackboard.playerpos.y - enemy.pos.y);
         let length = ((vector.x * vector.x + vector.y * vector.y) as f64).sqrt();
@@ -369,7 +451,8 @@ impl Enemy {
     }
 
     pub fn player_close(enemy: & mut Enemy, blackboard: &BlackBoard) -> bool{
-        if enemy.is_ranged  && Enemy::distance_to_player(enemy, blackboard) < 300.0{
+        // Ranged enemies need a clear shot, otherwise they keep chasing to find one
+        if enemy.is_ranged  && Enemy::distance_to_player(enemy, blackboard) < 300.0 && Enemy::player_in_sight(enemy, blackboard){
                 return true;
         } else {
             if enemy.box_es.get_walkbox(enemy.pos).has_intersection(blackboard.player_box.get_walkbox(blackboard.playerpos)) {
@@ -381,6 +464,15 @@ impl Enemy {
             }
         }
     }
+
+    pub fn player_in_sight(enemy: & mut Enemy, blackboard: &BlackBoard) -> bool {
+        blackboard.player_in_sight(enemy.pos)
+    }
+
+    // An enemy is in cover when there's a rock or wall between it and the player
+    pub fn in_cover(enemy: & mut Enemy, blackboard: &BlackBoard) -> bool {
+        !Enemy::player_in_sight(enemy, blackboard)
+    }
     // Using Connor's player implementation for this design:
 
     pub fn type_eq(a: EnemyKind, b: EnemyKind) -> bool{
@@ -411,6 +503,24 @@ impl Enemy {
             EnemyKind::Final => {
                 return 3;
             }
+            EnemyKind::Flying => {
+                return 4;
+            }
+            EnemyKind::Charger => {
+                return 5;
+            }
+            EnemyKind::Turret => {
+                return 6;
+            }
+            EnemyKind::Summoner => {
+                return 7;
+            }
+            EnemyKind::Splitter => {
+                return 8;
+            }
+            EnemyKind::Splitling => {
+                return 9;
+            }
         }
     }
 
@@ -673,19 +783,72 @@ impl Enemy {
         }
     }*/
 
-    pub fn move_projectile(&mut self){
-        //Moves all the attacks that this enemy shot
+    pub fn move_projectile(&mut self, room: &Room, target: Vec2<f32>){
+        //Moves all the attacks that this enemy shot. target is where homing shots steer to.
 
         let mut index = 0;
         let mut to_remove = Vec::new();
-        for mut atk in &mut self.atk_list {
-            atk.pos.x += atk.movement_vec.x * atk.speed * self.time_scale;
-            atk.pos.y += atk.movement_vec.y * atk.speed * self.time_scale;
-
-            //If the attack is off screen, remove it from the atk vector
+        let mut pieces = Vec::new();
+        for atk in &mut self.atk_list {
+            let mut prev = atk.pos;
+            atk.step(target, self.time_scale);
+
+            //Check the path it took in small steps so fast shots can't skip over a rock
+            let mut hit = false;
+            if !atk.landed {
+                let dx = atk.pos.x - prev.x;
+                let dy = atk.pos.y - prev.y;
+                let steps = ((dx * dx + dy * dy).sqrt() / MAX_SUBSTEP).ceil().max(1.0) as u32;
+                for i in 1..=steps {
+                    let point = Vec2::new(prev.x + dx * i as f32 / steps as f32, prev.y + dy * i as f32 / steps as f32);
+                    if room.blocks_projectile(point) {
+                        //Everything after the first blocked point never happened
+                        atk.pos = point;
+                        hit = true;
+                        break;
+                    }
+                    if i < steps {
+                        prev = point;
+                    }
+                }
+            }
 
-            //if !BlackBoard::is_walkable(current_tile)
-            if atk.pos.x < 0.0 || atk.pos.y < 0.0 || atk.pos.x > WINDOW_WIDTH as f32|| atk.pos.y > WINDOW_HEIGHT as f32
+            //If the attack hit a rock or wall, see what it does about it
+            if hit {
+                match atk.behavior {
+                    ProjectileBehavior::Bouncing(left) if left > 0 => {
+                        //Flip whichever way took it into the wall, both if it hit a corner
+                        let hit_x = room.blocks_projectile(Vec2::new(atk.pos.x, prev.y));
+                        let hit_y = room.blocks_projectile(Vec2::new(prev.x, atk.pos.y));
+                        if hit_x || !hit_y {
+                            atk.movement_vec.x = -atk.movement_vec.x;
+                        }
+                        if hit_y || !hit_x {
+                            atk.movement_vec.y = -atk.movement_vec.y;
+                        }
+                        atk.pos = prev;
+                        atk.base_pos = prev;
+                        atk.behavior = ProjectileBehavior::Bouncing(left - 1);
+                    }
+                    ProjectileBehavior::Puddle(_) => {
+                        //Lands just in front of the wall
+                        atk.pos = prev;
+                        atk.base_pos = prev;
+                        atk.land();
+                    }
+                    ProjectileBehavior::Splitting(count) => {
+                        pieces.append(&mut atk.split_pieces(count, prev));
+                        self.atk_impacts.push(AtkImpact::new(atk));
+                        to_remove.push(index);
+                    }
+                    _ => {
+                        self.atk_impacts.push(AtkImpact::new(atk));
+                        to_remove.push(index);
+                    }
+                }
+            }
+            //If the attack is off screen or a puddle has dried up, remove it from the atk vector
+            else if atk.is_finished() || atk.pos.x < 0.0 || atk.pos.y < 0.0 || atk.pos.x > WINDOW_WIDTH as f32|| atk.pos.y > WINDOW_HEIGHT as f32
             {
                 to_remove.push(index);
             }
@@ -697,76 +860,21 @@ impl Enemy {
             offset += 1;
             //println!("Bullet Scooby Removed");
         }
+        self.atk_list.append(&mut pieces);
+
+        self.atk_impacts.retain(|impact| !impact.is_finished());
     }
 
     //pub fn set_dir(& mut self, new_dir: Direction) { self.dir = new_dir; }
     //pub fn get_dir(& mut self) -> Direction { self.dir }
 }
 
-pub fn speed_kind(kind: EnemyKind) -> f32 {
-    let speed:f32;
-    match kind {
-        EnemyKind::Health => {
-            speed = 1.8;
-        }
-        EnemyKind::Speed =>{
-            speed = 3.8;
-        }
-        EnemyKind::Attack => {
-            speed = 2.8;
-        }
-        EnemyKind::Final => {
-            speed = 0.75;
-        }
-    }
-    return speed;
-}
-pub fn health_kind(kind: EnemyKind) -> i32 {
-    let health:i32;
-    match kind {
-        EnemyKind::Health => {
-            health = 5;
-        }
-        EnemyKind::Speed =>{
-            health = 2;
-        }
-        EnemyKind::Attack => {
-            health = 3;
-        }
-        EnemyKind::Final => {
-            health = 20;
-        }
-
-    }
-    return health;
-}
-
-pub fn set_ranged() -> bool {
+pub fn set_ranged(kind: EnemyKind) -> bool {
     let mut rng = rand::thread_rng();
-
-    match rng.gen_range( 0 ..= 6 ){
-               0 | 1 | 2 | 3 | 4 => {
-                   return false;
-               },
-               _ => {return true;}
-           }
+    rng.gen::<f32>() < enemy_def(kind).ranged_chance
 }
 
 pub fn box_kind(kind: EnemyKind) -> Box {
-    match kind {
-        EnemyKind::Health => {
-            return Box::new(Vec2::new(40, 30), Vec2::new(40, 40), Vec2::new(40, 30));
-        }
-        EnemyKind::Speed =>{
-            return Box::new(Vec2::new(40, 30), Vec2::new(40, 40), Vec2::new(40, 30));
-        }
-        EnemyKind::Attack => {
-            return Box::new(Vec2::new(40, 30), Vec2::new(40, 40), Vec2::new(40, 30));
-        }
-        EnemyKind::Final => {
-            // Final doesn't attack itself so no attackbox
-            return Box::new(Vec2::new(150, 100), Vec2::new(60, 60), Vec2::new(0, 0));
-        }
-    }
-
+    let def = enemy_def(kind);
+    Box::new(def.hitbox, def.walkbox, Vec2::new(def.attackbox.x as i32, def.attackbox.y as i32)).with_layer(Layer::Enemy)
 }
@@ -2,6 +2,10 @@ use crate::room::*;
 use crate::entity::*;
 use crate::util::*;
 use crate::tile::*;
+use crate::elite::*;
+use crate::miniboss::*;
+use crate::loot::*;
+use crate::world::CRATE_LOOT_CHANCE;
 //use crate::procgen::*;
 use rand::Rng;
 
@@ -56,7 +60,8 @@ impl Floor {
         Floor { rooms }
     }
 
-    pub fn gen_floor() -> Floor {
+    // depth is which floor this is, starting at 0. Deeper floors have more elites.
+    pub fn gen_floor(depth: usize) -> Floor {
         let mut rng = rand::thread_rng();
         let start_room = [
                 //                                  MID
@@ -204,19 +209,29 @@ impl Floor {
 
             let mut enemies = Vec::new();
             let current_room = &mut rooms[dy as usize][dx as usize];
-            let num_enemies = rng.gen_range(0 .. 4);
+            let mut num_enemies = rng.gen_range(0 .. 4);
+            // The end room's trapdoor is guarded by a mini-boss, with fewer enemies around it
+            if (dx, dy) == *procgen_res.last().unwrap() {
+                enemies.push(make_mini_boss(Vec2::new((LEFT_WALL + 8 * 64) as f32 + 32.0, (TOP_WALL + 3 * 64) as f32 + 40.0), depth));
+                num_enemies = num_enemies.min(1);
+            }
             for _i in 0 ..= num_enemies {
                 let boundary_x = rng.gen_range(3 ..= 13);
                 let boundary_y = rng.gen_range(2 ..= 8);
-                let enemy_rand: EnemyKind = match rng.gen_range(0 .. 3) {
-                    0 => EnemyKind::Attack,
-                    1 => EnemyKind::Health,
-                    2 => EnemyKind::Speed,
-                    _ => EnemyKind::Health,
-                };
-                enemies.push(Enemy::new( Vec2::new((LEFT_WALL + boundary_x * 64) as f32 + 32.0, (TOP_WALL + boundary_y * 64) as f32 + 40.0), enemy_rand));
+                enemies.push(room_enemy(Vec2::new(boundary_x, boundary_y), depth));
             }
             current_room.add_enemies(enemies);
+
+            // A couple of crates to smash, some with something inside. Kept clear of the doors so
+            // they never block the way through the room.
+            for _i in 0 .. rng.gen_range(0 ..= 2) {
+                let tile = Vec2::new(rng.gen_range(2 ..= 14), rng.gen_range(2 ..= 8));
+                if !current_room.crate_fits(tile) {
+                    continue;
+                }
+                let loot = if rng.gen::<f32>() < CRATE_LOOT_CHANCE { roll(&CLEAR_REWARDS) } else { Loot::Nothing };
+                current_room.props.spawn_crate(Vec2::new((LEFT_WALL + tile.x * 64) as f32 + 32.0, (TOP_WALL + tile.y * 64) as f32 + 40.0), loot);
+            }
         }
 
 
@@ -364,3 +379,24 @@ impl Floor {
         Floor { rooms }
     }
 }
+
+// A normal room enemy standing on tile (in tile coordinates), maybe an elite. Used for filling
+// rooms when the floor is made and by the difficulty director when it adds enemies.
+pub fn room_enemy(tile: Vec2<i32>, depth: usize) -> Enemy {
+    let mut rng = rand::thread_rng();
+    // Flyers and the special archetypes are rarer than the three slimes, which keep an even split
+    let kind: EnemyKind = match rng.gen_range(0 .. 11) {
+        0 | 1 => EnemyKind::Attack,
+        2 | 3 => EnemyKind::Health,
+        4 | 5 => EnemyKind::Speed,
+        6 => EnemyKind::Flying,
+        7 => EnemyKind::Charger,
+        8 => EnemyKind::Turret,
+        9 => EnemyKind::Summoner,
+        10 => EnemyKind::Splitter,
+        _ => EnemyKind::Health,
+    };
+    let mut enemy = Enemy::new( Vec2::new((LEFT_WALL + tile.x * 64) as f32 + 32.0, (TOP_WALL + tile.y * 64) as f32 + 40.0), kind);
+    roll_elite(&mut enemy, depth);
+    enemy
+}
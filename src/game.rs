@@ -3,6 +3,7 @@ use crate::player::*;
 use crate::map::*;
 use crate::room::*;
 use crate::util::*;
+use crate::director::*;
 
 use std::time::Instant;
 
@@ -24,6 +25,9 @@ pub struct Game {
     pub changed_floors: bool,
 
     pub init_time: Instant,
+
+    // Adjusts rooms to how the player is doing
+    pub director: Director,
 }
 
 impl Game {
@@ -38,6 +42,7 @@ impl Game {
             trans_dir: Direction::Up,
             changed_floors: false,
             init_time: Instant::now(),
+            director: Director::new(DIRECTOR_INTENSITY, true),
             //cr: Vec2::new(1, 3),
         }
     }
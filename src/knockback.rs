@@ -0,0 +1,135 @@
+use crate::util::*;
+use crate::entity::*;
+use crate::tile::*;
+use crate::room::{ROOM_WIDTH, ROOM_HEIGHT};
+use std::time::Instant;
+
+// Knockback and hit-stun. Anything that takes a hit gets shoved away from whatever hit it, and
+// enemies are stunned for a moment, which cancels whatever they were doing. Enemies knocked back
+// can be sent over pits (they fall in) and onto spikes (they get hurt).
+
+// Starting speed (px per frame) of the shove from a swing, a bomb, and an enemy hitting the player
+pub const SWING_KNOCKBACK: f32 = 9.0;
+pub const BOMB_KNOCKBACK: f32 = 16.0;
+pub const PLAYER_KNOCKBACK: f32 = 8.0;
+// A charged strike sends things further than a swing
+pub const CHARGE_KNOCKBACK: f32 = 13.0;
+// Shots push the player less than being hit up close
+pub const SHOT_KNOCKBACK: f32 = 5.0;
+// Fraction of the shove's speed kept each frame, and the speed it stops at
+pub const KNOCKBACK_DECAY: f32 = 0.8;
+pub const KNOCKBACK_STOP: f32 = 0.3;
+// Mini-bosses are heavy and only get shoved this much, the boss doesn't move at all
+pub const MINI_BOSS_KNOCKBACK_MULT: f32 = 0.5;
+// Time (ms) an enemy can't do anything after being hit
+pub const HIT_STUN_TIME: u128 = 250;
+// Damage from being knocked onto spikes, once per shove
+pub const SPIKE_KNOCKBACK_DAMAGE: i32 = 1;
+
+// Enemy component, the boss doesn't get one
+#[derive(Copy, Clone, Debug)]
+pub struct Knockback {
+    pub velocity: Vec2<f32>, // Velocity of the shove from the last hit, zero once it's stopped
+    pub stunned_at: Option<Instant>, // When the last hit stunned it
+    pub hit_spikes: bool, // Already hurt by spikes this shove
+}
+
+impl Knockback {
+    pub fn new() -> Knockback {
+        Knockback { velocity: Vec2::new(0.0, 0.0), stunned_at: None, hit_spikes: false }
+    }
+}
+
+// Velocity of a shove of force away from from, for something standing at to
+pub fn push_from(from: Vec2<f32>, to: Vec2<f32>, force: f32) -> Vec2<f32> {
+    let dx = to.x - from.x;
+    let dy = to.y - from.y;
+    let length = (dx * dx + dy * dy).sqrt();
+    // Right on top of each other, pick a side
+    if length < 0.01 {
+        return Vec2::new(force, 0.0);
+    }
+    Vec2::new(dx / length * force, dy / length * force)
+}
+
+// Moves pos by one frame of the shove and slows it down
+pub fn step(velocity: &mut Vec2<f32>, pos: &mut Vec2<f32>, time_scale: f32) {
+    if velocity.x == 0.0 && velocity.y == 0.0 {
+        return;
+    }
+    pos.x += velocity.x * time_scale;
+    pos.y += velocity.y * time_scale;
+    let keep = KNOCKBACK_DECAY.powf(time_scale);
+    velocity.x *= keep;
+    velocity.y *= keep;
+    if (velocity.x * velocity.x + velocity.y * velocity.y).sqrt() < KNOCKBACK_STOP {
+        *velocity = Vec2::new(0.0, 0.0);
+    }
+}
+
+// Shoves and stuns an enemy that just got hit from from
+pub fn knock_enemy(enemy: &mut Enemy, from: Vec2<f32>, force: f32) {
+    let force = if enemy.mini_boss { force * MINI_BOSS_KNOCKBACK_MULT } else { force };
+    let pos = enemy.pos;
+    match enemy.knockback {
+        Some( ref mut knockback ) => {
+            knockback.velocity = push_from(from, pos, force);
+            knockback.stunned_at = Some(Instant::now());
+            knockback.hit_spikes = false;
+        }
+        None => return,
+    }
+    // Being hit cancels wind-ups, charges and summons
+    if enemy.state == State::Attack {
+        enemy.state = State::Chase;
+    }
+}
+
+pub fn stunned(enemy: &Enemy) -> bool {
+    match enemy.knockback {
+        Some( Knockback { stunned_at: Some( time ), .. } ) => time.elapsed().as_millis() < HIT_STUN_TIME,
+        _ => false,
+    }
+}
+
+// True while the enemy is still sliding from a shove
+pub fn knocked(enemy: &Enemy) -> bool {
+    match enemy.knockback {
+        Some( knockback ) => knockback.velocity.x != 0.0 || knockback.velocity.y != 0.0,
+        None => false,
+    }
+}
+
+// Called after a knocked back enemy has been moved. Walkers over a pit fall in, anything on spikes
+// gets hurt. Flyers and phasers go over both.
+pub fn land_on_hazard(enemy: &mut Enemy, tiles: &[Vec<std::boxed::Box<dyn Tile>>]) {
+    if enemy.death || enemy.layer != MovementLayer::Walker || tiles.is_empty() {
+        return;
+    }
+    let x = (enemy.pos.x as i32 - LEFT_WALL).div_euclid(TILE_WIDTH);
+    let y = (enemy.pos.y as i32 - TOP_WALL).div_euclid(TILE_WIDTH);
+    if x < 0 || y < 0 || x >= ROOM_WIDTH || y >= ROOM_HEIGHT {
+        return;
+    }
+    match tiles[y as usize][x as usize].walkability() {
+        Walkability::Pit => {
+            // Enough to get through armour
+            let hp = enemy.hp;
+            enemy.damage(hp * 2);
+            match enemy.knockback {
+                Some( ref mut knockback ) => knockback.velocity = Vec2::new(0.0, 0.0),
+                None => {}
+            }
+        }
+        Walkability::Spike => {
+            match enemy.knockback {
+                Some( ref mut knockback ) if !knockback.hit_spikes => {
+                    knockback.hit_spikes = true;
+                    enemy.damage(SPIKE_KNOCKBACK_DAMAGE);
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,82 @@
+use crate::util::*;
+use crate::entity::*;
+use crate::enemydef::*;
+use crate::room::*;
+use crate::tile::*;
+use rand::Rng;
+
+// Loot. Every enemy rolls its kind's loot table (the loot key in assets/enemies.txt) when it dies,
+// and clearing a room always pays out one roll of CLEAR_REWARDS on top of the room's gem.
+
+// Weighted table for clearing a room, never empty handed
+pub const CLEAR_REWARDS: [(Loot, u32); 4] = [
+    (Loot::HalfHeart, 3),
+    (Loot::Heart, 2),
+    (Loot::Bomb, 2),
+    (Loot::Coin, 3),
+];
+
+// Picks one entry from a weighted table. Nothing if the table is empty.
+pub fn roll(table: &[(Loot, u32)]) -> Loot {
+    let total: u32 = table.iter().map(|t| t.1).sum();
+    if total == 0 {
+        return Loot::Nothing;
+    }
+    let mut pick = rand::thread_rng().gen_range(0..total);
+    for (loot, weight) in table.iter() {
+        if pick < *weight {
+            return *loot;
+        }
+        pick -= weight;
+    }
+    Loot::Nothing
+}
+
+// Puts loot on tile (in tile coordinates), or next to it if something's already there
+pub fn place_loot(room: & mut Room, tile: Vec2<i32>, loot: Loot) {
+    place_loot_clear_of(room, tile, loot, None);
+}
+
+// place_loot that never puts it on avoid, e.g. the player's tile, where walkover would pick it up
+// before it was ever seen
+pub fn place_loot_clear_of(room: & mut Room, tile: Vec2<i32>, loot: Loot, avoid: Option<Vec2<i32>>) {
+    if loot == Loot::Nothing {
+        return;
+    }
+    let spot = if tile_is_free(room, tile) && avoid != Some(tile) {
+        Some(tile)
+    } else {
+        room.free_tile_near(tile, avoid)
+    };
+    match spot {
+        Some( free ) => room.tiles[free.y as usize][free.x as usize] = std::boxed::Box::new(Pickup { loot }),
+        None => {}
+    }
+}
+
+fn tile_is_free(room: &Room, tile: Vec2<i32>) -> bool {
+    if tile.x <= 0 || tile.y <= 0 || tile.x >= ROOM_WIDTH - 1 || tile.y >= ROOM_HEIGHT - 1 {
+        return false;
+    }
+    let t = &room.tiles[tile.y as usize][tile.x as usize];
+    t.walkability() == Walkability::Floor && t.sprite() == SpriteID::Ground && !t.has_gem()
+}
+
+// Called once for every enemy that dies
+pub fn drop_enemy_loot(room: & mut Room, enemy: &Enemy) {
+    let tile = Vec2::new(
+        (enemy.get_pos_x() - LEFT_WALL) / TILE_WIDTH,
+        (enemy.get_pos_y() - TOP_WALL) / TILE_WIDTH
+    );
+    place_loot(room, tile, roll(&enemy_def(enemy.kind).loot));
+}
+
+// Called once when a room's last enemy dies. bonus_chance is the chance of an extra half heart,
+// which the difficulty director raises when the player is struggling.
+// The reward goes on tile, or around it, but never on player_tile.
+pub fn drop_clear_reward(room: & mut Room, tile: Vec2<i32>, player_tile: Vec2<i32>, bonus_chance: f32) {
+    place_loot_clear_of(room, tile, roll(&CLEAR_REWARDS), Some(player_tile));
+    if rand::thread_rng().gen::<f32>() < bonus_chance {
+        place_loot_clear_of(room, tile, Loot::HalfHeart, Some(player_tile));
+    }
+}
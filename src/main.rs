@@ -13,12 +13,25 @@ mod attack;
 
 mod blackboard;
 use crate::blackboard::*;
+mod behavior;
+mod perception;
+mod enemydef;
+mod elite;
+mod miniboss;
+mod director;
+mod loot;
 
 mod yellowenemy;
 mod redenemy;
 mod blueenemy;
+mod purpleenemy;
+mod chargerenemy;
+mod turretenemy;
+mod summonerenemy;
+mod splitterenemy;
 
 mod finalenemy;
+mod bosspattern;
 //use crate::finalenemy::*;
 
 mod util;
@@ -35,6 +48,12 @@ mod procgen;
 mod room;
 mod tile;
 mod boxes;
+mod navigation;
+mod arena;
+mod steering;
+mod knockback;
+mod spatial;
+mod world;
 
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
@@ -43,6 +62,8 @@ use roguelike::SDLCore;
 use roguelike::Demo;
 use entity::Health;
 use player::PowerUp;
+use loot::place_loot;
+use navigation::tile_of;
 
 use entity::*;
 
@@ -74,12 +95,16 @@ pub struct Manager {
     prev_frame: Instant,
     cur_frame: Instant,
     time_scale: f32,
+    hard_mode: bool, // Turns the difficulty director off
+    director_intensity: f32, // Picked on the main menu, every new game's director uses it
 }
 
 impl Demo for Manager {
     // Initialize manager struct.
     fn init() -> Result<Self, String> {
         let core = SDLCore::init(TITLE, VSYNC, WINDOW_WIDTH, WINDOW_HEIGHT)?;
+        // Needs to happen before Game::new() makes the first floor's enemies
+        enemydef::load_enemy_defs(enemydef::ENEMY_DEF_PATH)?;
         let debug = false;
         let menu = MenuState::MainMenu;
         let blackboard = BlackBoard::new();
@@ -89,8 +114,10 @@ impl Demo for Manager {
         let prev_frame = Instant::now();
         let cur_frame = Instant::now();
         let time_scale = 1.0;
+        let hard_mode = false;
+        let director_intensity = director::DIRECTOR_INTENSITY;
 
-        Ok(Manager{core, debug, menu, game, blackboard, prev_frame, cur_frame, time_scale })
+        Ok(Manager{core, debug, menu, game, blackboard, prev_frame, cur_frame, time_scale, hard_mode, director_intensity })
     }
 
     fn run(&mut self) -> Result<(), String> {
@@ -104,6 +131,7 @@ impl Demo for Manager {
         println!("\t2\t\tTurn ON debug graphics");
         println!("\t3\t\tLock doors");
         println!("\t4\t\tUnlock doors");
+        println!("\t5\t\tDrop the room's mini-boss into a pit");
         println!("");
         println!("\tW\t\tMove Up");
         println!("\tS\t\tMove Down");
@@ -116,6 +144,9 @@ impl Demo for Manager {
         println!("\tRight Arrow\tLook Right");
         println!("");
         println!("\tSpace\t\tShort-range attack (cardinal directions only)");
+        println!("\tLShift+Arrow\tHold to charge, release to strike");
+        println!("\tE\t\tDodge roll (brief invincibility)");
+        println!("\tReturn\t\tStart in hard mode (from the main menu)");
         println!("\tEscape\t\tPause game (while in game, not menus)");
         println!("");
         println!("\tQ\t\tUse Bomb");
@@ -169,6 +200,15 @@ impl Demo for Manager {
                         { self.game.player.plus_power_speed();
                             //println!("PowerupSpeed is {}", self.game.player.power_up_vec[1]);
                         },
+                    // Up and down on the main menu change how hard the difficulty director pushes
+                    Event::KeyUp {keycode: Some(Keycode::Up), repeat: false, ..} if matches!(self.menu, MenuState::MainMenu) =>
+                        { self.director_intensity = (self.director_intensity + director::DIRECTOR_INTENSITY_STEP).min(director::MAX_DIRECTOR_INTENSITY);
+                            self.game.director.intensity = self.director_intensity;
+                        },
+                    Event::KeyUp {keycode: Some(Keycode::Down), repeat: false, ..} if matches!(self.menu, MenuState::MainMenu) =>
+                        { self.director_intensity = (self.director_intensity - director::DIRECTOR_INTENSITY_STEP).max(0.0);
+                            self.game.director.intensity = self.director_intensity;
+                        },
                     Event::KeyUp {keycode: Some(Keycode::K), repeat: false, ..} =>
                         { self.game.player.plus_power_attack();
                             //println!("PowerupAttack is {}", self.game.player.power_up_vec[2]);
@@ -202,9 +242,13 @@ impl Demo for Manager {
                 }
 
                 MainMenu => {
-                    if keystate.contains(&Keycode::Space) {
+                    // Return starts hard mode, where the difficulty director leaves rooms alone
+                    if keystate.contains(&Keycode::Space) || keystate.contains(&Keycode::Return) {
+                        self.hard_mode = keystate.contains(&Keycode::Return);
                         self.menu = GameActive;
                         self.game = Game::new(); // Initialize a new game
+                        self.game.director.enabled = !self.hard_mode;
+                        self.game.director.intensity = self.director_intensity;
                         // This makes it so exiting to menu and staring the game again works
                         // properly
                         //println!("Switch");
@@ -299,6 +343,10 @@ impl Demo for Manager {
                                 self.game.current_room_mut().tiles[0][8].unlock();
                                 self.game.current_room_mut().tiles[10][8].unlock();
                             }
+                            // Knock the room's mini-boss into a pit, its reward should still drop
+                            if keystate.contains(&Keycode::Num5) {
+                                miniboss::debug_drop_in_pit(self.game.current_room_mut());
+                            }
 
 
                             // -------------------------------------- GAMEPLAY CODE -------------------------
@@ -316,61 +364,67 @@ impl Demo for Manager {
                             if keystate.contains(&Keycode::Left)  { if !self.game.player.is_charging && !self.game.player.is_attacking {self.game.player.set_dir(Direction::Left);}  }
                             if keystate.contains(&Keycode::Right) { if !self.game.player.is_charging && !self.game.player.is_attacking {self.game.player.set_dir(Direction::Right);} }
 
+                            // Charged attack: hold LShift and an arrow to build it up, let go of either to strike
+                            if self.game.player.holding_charge() && !(keystate.contains(&Keycode::LShift) &&
+                                (keystate.contains(&Keycode::Up) || keystate.contains(&Keycode::Down) ||
+                                keystate.contains(&Keycode::Left) || keystate.contains(&Keycode::Right))) {
+                                self.game.player.release_charge();
+                            }
+
                             if keystate.contains(&Keycode::Up) && matches!(self.menu, MenuState::GameActive) &&
-                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging {
+                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging && !self.game.player.holding_charge() {
                                 self.game.player.set_dir(Direction::Up);
                                 if keystate.contains(&Keycode::LShift){
-                                    //self.game.player.signal_charge();
-                                    self.game.player.signal_attack();
+                                    self.game.player.start_charge();
                                 }
                                 else{
                                     self.game.player.signal_attack();
                                 }
                             }
                             if keystate.contains(&Keycode::Down) && matches!(self.menu, MenuState::GameActive) &&
-                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging {
+                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging && !self.game.player.holding_charge() {
                                 self.game.player.set_dir(Direction::Down);
                                 if keystate.contains(&Keycode::LShift){
-                                    //maybe we add charge for up and down?
-                                    //self.game.player.signal_charge();
-                                    self.game.player.signal_attack();
+                                    self.game.player.start_charge();
                                 }
                                 else{
                                     self.game.player.signal_attack();
                                 }
                             }
                             if keystate.contains(&Keycode::Left) && matches!(self.menu, MenuState::GameActive) &&
-                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging{
+                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging && !self.game.player.holding_charge() {
                                 self.game.player.set_dir(Direction::Left);
                                 if keystate.contains(&Keycode::LShift){
-                                    self.game.player.signal_charge();
-                                    self.game.player.signal_attack();
+                                    self.game.player.start_charge();
                                 }
                                 else{
                                     self.game.player.signal_attack();
                                 }
                             }
                             if keystate.contains(&Keycode::Right) && matches!(self.menu, MenuState::GameActive) &&
-                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging {
+                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.is_charging && !self.game.player.holding_charge() {
                                 self.game.player.set_dir(Direction::Right);
                                 if keystate.contains(&Keycode::LShift){
-                                    self.game.player.signal_charge();
-                                    self.game.player.signal_attack();
+                                    self.game.player.start_charge();
                                 }
                                 else{
                                     self.game.player.signal_attack();
                                 }
-
                             }
 
 
 
                             // Attack without changing direction
                             if keystate.contains(&Keycode::Space) && matches!(self.menu, MenuState::GameActive) &&
-                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking {
+                                self.game.player.last_attack_time.unwrap().elapsed() >= Duration::from_millis(800) && !self.game.player.is_attacking && !self.game.player.holding_charge() {
                                 self.game.player.signal_attack();
                             }
 
+                            // Dodge roll the way the player is walking
+                            if keystate.contains(&Keycode::E) && matches!(self.menu, MenuState::GameActive) {
+                                self.game.player.dodge(mov_vec);
+                            }
+
                             if keystate.contains(&Keycode::Q) && matches!(self.menu, MenuState::GameActive) &&
                                 self.game.init_time.elapsed() >= Duration::from_secs(1) && self.game.player.has_bomb {
                                 self.game.player.use_bomb();
@@ -391,63 +445,37 @@ impl Demo for Manager {
                             // Move player
                             self.game.player.update_pos(mov_vec);
                             //Update enemy
-                            let mut enemy_to_push = Enemy::new(Vec2::new(0.0, 0.0), EnemyKind::Speed);
-                            let mut enemy_to_push2 = Enemy::new(Vec2::new(0.0, 0.0), EnemyKind::Speed);
-                            let mut push_enemy = false;
                             let mut boss_dead = false;
                             let mut v = vec![];
 
                             for enemy in self.game.current_room_mut().enemies.iter_mut() {
                                 if !enemy.death{
                                     enemy.update(& self.blackboard);
-                                    v.push(enemy.clone());
+                                    // Only the walkbox and healing flag are needed from the ones already updated
+                                    v.push((enemy.box_es.get_walkbox(enemy.pos), enemy.is_healing));
                                     let enemy_walkbox = enemy.box_es.get_walkbox(enemy.pos);
-                                    for enemy_walk in v.iter(){
-                                        let enemy_walk_walkbox = enemy_walk.box_es.get_walkbox(enemy_walk.pos);
-                                        // to stop overlap of enemies (Doesn't look good whatever we tried)
-                                        // if (enemy.kind == enemy_walk.kind) && (enemy.pos != enemy_walk.pos) && (enemy_walkbox.has_intersection(enemy_walk_walkbox)){
-                                        //     println!("inter_rect");
-                                        //     collision::enemy_collision(enemy, &(enemy_walk.current_frame_tile.x), &(enemy_walk.current_frame_tile.y));
-                                        //     //enemy.state = State::Idle;
-                                        // }
-                                        if (enemy_walkbox != enemy_walk_walkbox) && enemy.kind == EnemyKind::Health && enemy_walk.is_healing && enemy_walkbox.has_intersection(enemy_walk_walkbox) {
+                                    for (enemy_walk_walkbox, enemy_walk_healing) in v.iter(){
+                                        if (enemy_walkbox != *enemy_walk_walkbox) && enemy.kind == EnemyKind::Health && *enemy_walk_healing && enemy_walkbox.has_intersection(*enemy_walk_walkbox) {
                                             // if red enemy is intersection with another enemy that is 'healing'
                                             enemy.is_healing = true;
                                             enemy.state = State::Heal;
                                         }
                                     }
 
-
-                                    if enemy.kind == EnemyKind::Final && enemy.is_attacking{ //Final Boss Check
-                                        //println!("Before pops");
-
-                                        match enemy.final_enemies_to_spawn.pop() {
-                                            Some( new_enemy ) => enemy_to_push = new_enemy,
-                                            None => {},
-                                        };
-
-                                        //println!("First pop");
-
-                                        match enemy.final_enemies_to_spawn.pop() {
-                                            Some( new_enemy ) => enemy_to_push2 = new_enemy,
-                                            None => {},
-                                        };
-
-                                        //enemy_to_push2 = enemy.final_enemies_to_spawn.pop().unwrap();
-                                        //println!("Second pop");
-                                        push_enemy = true;
-                                        //self.game.current_room_mut().additional_enemies(enemy.final_enemies_to_spawn.pop().unwrap());
-                                        enemy.is_attacking = false;
-                                    }
                                 }
                                 if enemy.death && enemy.kind == EnemyKind::Final{
                                     boss_dead = true;
                                 }
                             }
-                            //FINAL BOSS ONLY
-                            if push_enemy {
-                                self.game.current_room_mut().additional_enemies(enemy_to_push);
-                                self.game.current_room_mut().additional_enemies(enemy_to_push2);
+                            // Summoned minions (the boss's too) and split-offs join the room, dead splitters included
+                            self.game.current_room_mut().add_pending_spawns();
+                            // Spread enemies out so they don't stack on each other or the player
+                            steering::separate(&mut self.game.current_room_mut().enemies, & self.blackboard);
+                            // Crates and other component entities, anything that broke leaves its loot
+                            let room = self.game.current_room_mut();
+                            let drops = world::update(&mut room.props, &room.tiles, self.time_scale);
+                            for (pos, loot) in drops {
+                                place_loot(room, tile_of(pos), loot);
                             }
                             if boss_dead {
                                 self.game.changed_floors = false;
@@ -457,6 +485,14 @@ impl Demo for Manager {
 
                             // Apply collision
                             self.collide();
+
+                            // Let the director see how the room is going
+                            director::update(&mut self.game);
+
+                            // Bombs can blow up rocks, so the enemies' copy of the room needs refreshing
+                            if self.game.player.using_bomb {
+                                self.blackboard.update_room(& self.game);
+                            }
                             // // debugging healing and damage to a PLAYER
                             // if keystate.contains(&Keycode::H) { self.game.player.heal(2);
                             //     println!("Health is: {}", self.game.player.health());
@@ -490,6 +526,8 @@ impl Demo for Manager {
                 GameOver => {
                     if keystate.contains(&Keycode::Space) {
                         self.game = Game::new();
+                        self.game.director.enabled = !self.hard_mode;
+                        self.game.director.intensity = self.director_intensity;
                         self.menu = GameActive;
                     }
                 }
@@ -530,7 +568,7 @@ impl Manager {
     // }
 
     fn collide(& mut self) {
-        collision::base(&mut self.game, &mut self.core, &mut self.menu, & self.blackboard);
+        collision::base(&mut self.game, &mut self.menu, & self.blackboard);
     }
 
     fn walkover(& mut self) {
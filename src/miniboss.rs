@@ -0,0 +1,84 @@
+use crate::util::*;
+use crate::entity::*;
+use crate::elite::*;
+use crate::room::*;
+use crate::loot::*;
+use crate::tile::{Tile, Pit};
+use crate::knockback::land_on_hazard;
+use rand::Rng;
+
+// Mini-bosses. Every floor before the boss has one guarding the trapdoor in its end room. It's a
+// bigger, tougher elite version of a normal archetype, and the trapdoor won't open until it's dead.
+
+// Kinds a mini-boss can be based on
+pub const MINI_BOSS_KINDS: [EnemyKind; 6] = [
+    EnemyKind::Attack,
+    EnemyKind::Health,
+    EnemyKind::Speed,
+    EnemyKind::Charger,
+    EnemyKind::Summoner,
+    EnemyKind::Splitter,
+];
+
+// Health is multiplied by this, plus a bit more every floor
+pub const MINI_BOSS_HP_MULT: i32 = 3;
+pub const MINI_BOSS_HP_PER_FLOOR: i32 = 2;
+// Boxes and sprite are scaled up by this
+pub const MINI_BOSS_SCALE: f32 = 1.5;
+// Moves a little slower than the normal version so the extra size is fair
+pub const MINI_BOSS_SPEED_MULT: f32 = 0.8;
+
+// Makes the mini-boss for a floor, standing at pos. depth is the floor index.
+pub fn make_mini_boss(pos: Vec2<f32>, depth: usize) -> Enemy {
+    let mut rng = rand::thread_rng();
+    let kind = MINI_BOSS_KINDS[rng.gen_range(0..MINI_BOSS_KINDS.len())];
+
+    let mut enemy = Enemy::new(pos, kind);
+    enemy.mini_boss = true;
+    enemy.m_hp = enemy.m_hp * MINI_BOSS_HP_MULT + MINI_BOSS_HP_PER_FLOOR * depth as i32;
+    enemy.hp = enemy.m_hp;
+    enemy.speed *= MINI_BOSS_SPEED_MULT;
+    enemy.box_es.hitbox = scale(enemy.box_es.hitbox);
+    enemy.box_es.walkbox = scale(enemy.box_es.walkbox);
+    enemy.box_es.attackbox = Vec2::new(
+        (enemy.box_es.attackbox.x as f32 * MINI_BOSS_SCALE) as i32,
+        (enemy.box_es.attackbox.y as f32 * MINI_BOSS_SCALE) as i32
+    );
+    make_elite(&mut enemy, MODIFIERS[rng.gen_range(0..MODIFIERS.len())]);
+    enemy
+}
+
+fn scale(size: Vec2<u32>) -> Vec2<u32> {
+    Vec2::new((size.x as f32 * MINI_BOSS_SCALE) as u32, (size.y as f32 * MINI_BOSS_SCALE) as u32)
+}
+
+// How big the sprite is drawn, mini-bosses are drawn bigger
+pub fn draw_scale(enemy: &Enemy) -> f32 {
+    if enemy.mini_boss { MINI_BOSS_SCALE } else { 1.0 }
+}
+
+// True while the room's mini-boss is still standing
+pub fn mini_boss_alive(room: &Room) -> bool {
+    room.enemies.iter().any(|e| e.mini_boss && !e.death)
+}
+
+// Guaranteed reward on top of the gem every elite drops: a bomb on a free tile next to where it died
+pub fn drop_mini_boss_reward(room: & mut Room, enemy: &Enemy) {
+    let tile_x = (enemy.get_pos_x() - LEFT_WALL) / TILE_WIDTH;
+    let tile_y = (enemy.get_pos_y() - TOP_WALL) / TILE_WIDTH;
+
+    place_loot(room, Vec2::new(tile_x, tile_y), Loot::Bomb);
+}
+
+// Debug (key 5). Drops every mini-boss in the room into a pit through land_on_hazard, the same way a
+// shove over a real pit does, so the reward can be checked for deaths that aren't from a hit
+pub fn debug_drop_in_pit(room: & mut Room) {
+    let pits: Vec<Vec<std::boxed::Box<dyn Tile>>> = (0..ROOM_HEIGHT)
+        .map(|_| (0..ROOM_WIDTH).map(|_| std::boxed::Box::new(Pit {}) as std::boxed::Box<dyn Tile>).collect())
+        .collect();
+    for enemy in room.enemies.iter_mut() {
+        if enemy.mini_boss {
+            land_on_hazard(enemy, &pits);
+        }
+    }
+}
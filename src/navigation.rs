@@ -0,0 +1,299 @@
+use crate::util::*;
+use crate::room::ROOM_HEIGHT;
+use crate::room::ROOM_WIDTH;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// Costs are scaled by 10 so diagonals (sqrt(2) ~ 1.4) can stay integers
+pub const STRAIGHT_COST: i32 = 10;
+pub const DIAGONAL_COST: i32 = 14;
+pub const UNREACHABLE: i32 = i32::MAX;
+
+// How many path tiles ahead an enemy will look to cut straight across open ground
+pub const PATH_LOOKAHEAD: usize = 3;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct NavSettings {
+    pub diagonal: bool,    // Allow moving to the 4 diagonal neighbours as well
+    pub cut_corners: bool, // Allow a diagonal move when one of the two tiles beside it is blocked
+}
+
+impl NavSettings {
+    pub fn new(diagonal: bool, cut_corners: bool) -> NavSettings {
+        NavSettings { diagonal, cut_corners }
+    }
+}
+
+pub fn in_bounds(tile: Vec2<i32>) -> bool {
+    tile.x >= 0 && tile.x < ROOM_WIDTH && tile.y >= 0 && tile.y < ROOM_HEIGHT
+}
+
+// Tile a pixel position is in. Uses floor division so positions left of/above the room don't
+// round into the first row/column.
+pub fn tile_of(pos: Vec2<f32>) -> Vec2<i32> {
+    Vec2::new(
+        (pos.x as i32 - LEFT_WALL).div_euclid(TILE_WIDTH),
+        (pos.y as i32 - TOP_WALL).div_euclid(TILE_WIDTH)
+    )
+}
+
+// All tiles that can be moved to from tile in one step, along with the cost of the step
+pub fn neighbors(tile: Vec2<i32>, settings: NavSettings, passable: &dyn Fn(Vec2<i32>) -> bool) -> Vec<(Vec2<i32>, i32)> {
+    let mut out = Vec::with_capacity(8);
+
+    for (dx, dy) in [(1, 0), (-1, 0), (0, -1), (0, 1)].iter() {
+        let next = Vec2::new(tile.x + dx, tile.y + dy);
+        if in_bounds(next) && passable(next) {
+            out.push((next, STRAIGHT_COST));
+        }
+    }
+
+    if settings.diagonal {
+        for (dx, dy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)].iter() {
+            let next = Vec2::new(tile.x + dx, tile.y + dy);
+            if !in_bounds(next) || !passable(next) {
+                continue;
+            }
+            let side_a = Vec2::new(tile.x + dx, tile.y);
+            let side_b = Vec2::new(tile.x, tile.y + dy);
+            let open_a = in_bounds(side_a) && passable(side_a);
+            let open_b = in_bounds(side_b) && passable(side_b);
+            // Never squeeze between two blocked tiles, and only clip one if cutting corners is on
+            if (open_a && open_b) || (settings.cut_corners && (open_a || open_b)) {
+                out.push((next, DIAGONAL_COST));
+            }
+        }
+    }
+
+    out
+}
+
+// Entry in the open list for Dijkstra / A*. Ordered so BinaryHeap pops the lowest cost first.
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Node {
+    cost: i32,
+    tile: (i32, i32),
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost).then_with(|| self.tile.cmp(&other.tile))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Distance to a target from every tile in the room. Built once a frame and shared by every enemy
+// heading for the same place, they just walk downhill from whatever tile they're on.
+pub struct FlowField {
+    pub target: Vec2<i32>,
+    pub settings: NavSettings,
+    pub cost: Vec<Vec<i32>>, // [y][x] like room tiles
+}
+
+impl FlowField {
+    pub fn new(settings: NavSettings) -> FlowField {
+        FlowField {
+            target: Vec2::new(-1, -1),
+            settings,
+            cost: vec![vec![UNREACHABLE; ROOM_WIDTH as usize]; ROOM_HEIGHT as usize],
+        }
+    }
+
+    pub fn build(&mut self, target: Vec2<i32>, passable: &dyn Fn(Vec2<i32>) -> bool) {
+        self.target = target;
+        for row in self.cost.iter_mut() {
+            for c in row.iter_mut() {
+                *c = UNREACHABLE;
+            }
+        }
+        if !in_bounds(target) {
+            return;
+        }
+
+        let mut open = BinaryHeap::new();
+        self.cost[target.y as usize][target.x as usize] = 0;
+        open.push(Node { cost: 0, tile: (target.x, target.y) });
+
+        while let Some(Node { cost, tile }) = open.pop() {
+            let tile = Vec2::new(tile.0, tile.1);
+            if cost > self.cost[tile.y as usize][tile.x as usize] {
+                continue;
+            }
+            for (next, step) in neighbors(tile, self.settings, passable) {
+                let new_cost = cost + step;
+                if new_cost < self.cost[next.y as usize][next.x as usize] {
+                    self.cost[next.y as usize][next.x as usize] = new_cost;
+                    open.push(Node { cost: new_cost, tile: (next.x, next.y) });
+                }
+            }
+        }
+    }
+
+    pub fn cost_at(&self, tile: Vec2<i32>) -> i32 {
+        if in_bounds(tile) {
+            self.cost[tile.y as usize][tile.x as usize]
+        } else {
+            UNREACHABLE
+        }
+    }
+
+    // Cheapest neighbour of from, or None if from is the target or can't reach it.
+    // from doesn't have to be passable itself, so enemies shoved into a rock can still get out.
+    pub fn next_tile(&self, from: Vec2<i32>, passable: &dyn Fn(Vec2<i32>) -> bool) -> Option<Vec2<i32>> {
+        if from == self.target {
+            return None;
+        }
+        let mut best: Option<(Vec2<i32>, i32)> = None;
+        for (next, _) in neighbors(from, self.settings, passable) {
+            let c = self.cost_at(next);
+            if c == UNREACHABLE {
+                continue;
+            }
+            match best {
+                Some((_, best_cost)) if best_cost <= c => {}
+                _ => best = Some((next, c)),
+            }
+        }
+        match best {
+            Some((tile, c)) if c < self.cost_at(from) || self.cost_at(from) == UNREACHABLE => Some(tile),
+            _ => None,
+        }
+    }
+
+    // Follows the field from a tile for up to len steps
+    pub fn path_from(&self, from: Vec2<i32>, len: usize, passable: &dyn Fn(Vec2<i32>) -> bool) -> Vec<Vec2<i32>> {
+        let mut path = Vec::new();
+        let mut cur = from;
+        while path.len() < len {
+            match self.next_tile(cur, passable) {
+                Some(next) => {
+                    path.push(next);
+                    cur = next;
+                }
+                None => break,
+            }
+        }
+        path
+    }
+}
+
+fn heuristic(a: Vec2<i32>, b: Vec2<i32>, settings: NavSettings) -> i32 {
+    let dx = (a.x - b.x).abs();
+    let dy = (a.y - b.y).abs();
+    if settings.diagonal {
+        // Octile distance
+        STRAIGHT_COST * (dx + dy) + (DIAGONAL_COST - 2 * STRAIGHT_COST) * dx.min(dy)
+    } else {
+        STRAIGHT_COST * (dx + dy)
+    }
+}
+
+// A* from start to goal. The returned path doesn't include start but does include goal.
+// Returns None when goal can't be reached.
+pub fn astar(start: Vec2<i32>, goal: Vec2<i32>, settings: NavSettings, passable: &dyn Fn(Vec2<i32>) -> bool) -> Option<Vec<Vec2<i32>>> {
+    if !in_bounds(start) || !in_bounds(goal) {
+        return None;
+    }
+    if start == goal {
+        return Some(Vec::new());
+    }
+
+    let none = Vec2::new(-1, -1);
+    let mut g = vec![vec![UNREACHABLE; ROOM_WIDTH as usize]; ROOM_HEIGHT as usize];
+    let mut parent = vec![vec![none; ROOM_WIDTH as usize]; ROOM_HEIGHT as usize];
+    let mut open = BinaryHeap::new();
+
+    g[start.y as usize][start.x as usize] = 0;
+    open.push(Node { cost: heuristic(start, goal, settings), tile: (start.x, start.y) });
+
+    while let Some(Node { cost, tile }) = open.pop() {
+        let tile = Vec2::new(tile.0, tile.1);
+        if tile == goal {
+            let mut path = Vec::new();
+            let mut cur = goal;
+            while cur != start {
+                path.push(cur);
+                cur = parent[cur.y as usize][cur.x as usize];
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let cur_g = g[tile.y as usize][tile.x as usize];
+        if cost > cur_g + heuristic(tile, goal, settings) {
+            continue; // Stale entry, already found a better way here
+        }
+
+        for (next, step) in neighbors(tile, settings, passable) {
+            let new_g = cur_g + step;
+            if new_g < g[next.y as usize][next.x as usize] {
+                g[next.y as usize][next.x as usize] = new_g;
+                parent[next.y as usize][next.x as usize] = tile;
+                open.push(Node { cost: new_g + heuristic(next, goal, settings), tile: (next.x, next.y) });
+            }
+        }
+    }
+
+    None
+}
+
+// Walks every tile the segment from -> to passes through (grid DDA) and checks none of them are
+// blocked. The tiles at each end are not checked. Positions are in pixels.
+pub fn line_clear(from: Vec2<f32>, to: Vec2<f32>, blocked: &dyn Fn(Vec2<i32>) -> bool) -> bool {
+    let tile_w = TILE_WIDTH as f32;
+    let start = Vec2::new((from.x - LEFT_WALL as f32) / tile_w, (from.y - TOP_WALL as f32) / tile_w);
+    let end = Vec2::new((to.x - LEFT_WALL as f32) / tile_w, (to.y - TOP_WALL as f32) / tile_w);
+
+    let mut tile = Vec2::new(start.x.floor() as i32, start.y.floor() as i32);
+    let end_tile = Vec2::new(end.x.floor() as i32, end.y.floor() as i32);
+
+    let dir = Vec2::new(end.x - start.x, end.y - start.y);
+    let step = Vec2::new(if dir.x < 0.0 { -1 } else { 1 }, if dir.y < 0.0 { -1 } else { 1 });
+
+    // How far along the ray (0.0 to 1.0) we need to go to cross one whole tile on each axis
+    let delta = Vec2::new(
+        if dir.x != 0.0 { (1.0 / dir.x).abs() } else { f32::INFINITY },
+        if dir.y != 0.0 { (1.0 / dir.y).abs() } else { f32::INFINITY },
+    );
+
+    // How far along the ray the first tile border on each axis is
+    let mut next = Vec2::new(
+        if dir.x < 0.0 { (start.x - tile.x as f32) * delta.x } else { (tile.x as f32 + 1.0 - start.x) * delta.x },
+        if dir.y < 0.0 { (start.y - tile.y as f32) * delta.y } else { (tile.y as f32 + 1.0 - start.y) * delta.y },
+    );
+
+    // Each step moves one tile on one axis, so this is exactly how many steps reach the end
+    let steps = (end_tile.x - tile.x).abs() + (end_tile.y - tile.y).abs();
+    for _ in 0..steps {
+        if next.x < next.y {
+            tile.x += step.x;
+            next.x += delta.x;
+        } else {
+            tile.y += step.y;
+            next.y += delta.y;
+        }
+
+        if tile != end_tile && blocked(tile) {
+            return false;
+        }
+    }
+
+    true
+}
+
+// Direction that best matches a movement vector, used for facing and attackboxes
+pub fn dir_from_vec(vec: Vec2<f32>, current: Direction) -> Direction {
+    if vec.x == 0.0 && vec.y == 0.0 {
+        return current;
+    }
+    if vec.x.abs() >= vec.y.abs() {
+        if vec.x > 0.0 { Direction::Right } else { Direction::Left }
+    } else {
+        if vec.y > 0.0 { Direction::Down } else { Direction::Up }
+    }
+}
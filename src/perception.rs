@@ -0,0 +1,313 @@
+use crate::blackboard::*;
+use crate::util::*;
+use crate::entity::*;
+use crate::navigation::*;
+use crate::room::*;
+use rand::Rng;
+use std::time::Instant;
+
+// Enemy perception. Enemies don't know where the player is until they notice them: they see in a
+// cone in front of them (rocks and walls block it), hear swings and bombs, and fill up an alert
+// meter while the player is in view. A full meter makes them aware and they give chase. Once they
+// lose the player for long enough the meter drains and they go searching where they last saw them.
+
+// How wide the vision cone is either side of where the enemy is facing, 70 degrees in radians
+pub const VISION_HALF_ANGLE: f32 = 1.22173;
+// Anything this close gets noticed whichever way the enemy is facing
+pub const FEEL_RANGE: f64 = 80.0;
+// How fast the meter fills per frame at the edge of sight range, up to three times faster up close
+pub const ALERT_GAIN: f32 = 0.02;
+// How fast it drains per frame when the player isn't in view and the enemy hasn't noticed them yet
+pub const ALERT_DECAY: f32 = 0.005;
+// How fast it drains per frame while an aware enemy has lost sight of the player, about 4 seconds
+pub const AWARE_DECAY: f32 = 0.004;
+// Hearing something puts the meter at least this high, enough to go look
+pub const HEARD_ALERT: f32 = 0.6;
+// Meter needed before an idle enemy goes to check out where it last saw or heard the player
+pub const SEARCH_ALERT: f32 = 0.35;
+// How far (px) swings and bombs can be heard
+pub const ATTACK_NOISE_RADIUS: f64 = 260.0;
+pub const BOMB_NOISE_RADIUS: f64 = 700.0;
+
+// Patrol routes: how many points, how far (tiles) from where the enemy started, how slow it walks
+// compared to chasing and how long (ms) it stops at each point
+pub const PATROL_POINTS: usize = 3;
+pub const PATROL_RADIUS: i32 = 3;
+pub const PATROL_SPEED_MULT: f32 = 0.5;
+pub const PATROL_PAUSE: u128 = 900;
+// Searching walks a bit faster than patrolling, then looks around this long (ms) before giving up
+pub const SEARCH_SPEED_MULT: f32 = 0.75;
+pub const SEARCH_LOOK_TIME: u128 = 2000;
+// Gives up searching after this long (ms) even if it never got there, e.g. the spot is over a pit
+pub const SEARCH_GIVE_UP_TIME: u128 = 7000;
+// How long (ms) draw shows the "!" after an enemy notices the player
+pub const NOTICED_TIME: u128 = 600;
+
+// Enemy component for the kinds that have to notice the player
+#[derive(Clone, Debug)]
+pub struct Perception {
+    pub alert: f32, // 0.0 to 1.0, fills while the enemy can see the player
+    pub aware: bool, // Has noticed the player and is fighting them
+    pub noticed_time: Option<Instant>, // When it last became aware, for the "!" above its head
+    pub last_known_pos: Option<Vec2<f32>>, // Where it last saw or heard the player
+    pub patrol: Vec<Vec2<f32>>, // Patrol route, made the first time it patrols
+    pub patrol_index: usize,
+    pub patrol_wait: Option<Instant>, // When it stopped at a patrol point or started looking around
+}
+
+impl Perception {
+    pub fn new() -> Perception {
+        Perception {
+            alert: 0.0,
+            aware: false,
+            noticed_time: None,
+            last_known_pos: None,
+            patrol: Vec::new(),
+            patrol_index: 0,
+            patrol_wait: None,
+        }
+    }
+
+    pub fn notice(&mut self) {
+        if !self.aware {
+            self.noticed_time = Some(Instant::now());
+        }
+        self.alert = 1.0;
+        self.aware = true;
+    }
+}
+
+// Called every frame before the enemy's state runs. range is how far it can see.
+pub fn perceive(enemy: & mut Enemy, blackboard: &BlackBoard, range: f64){
+    let dist = Enemy::distance_to_player(enemy, blackboard);
+    let sees = dist < FEEL_RANGE ||
+        (dist < range && in_vision_cone(enemy, blackboard.playerpos) && Enemy::player_in_sight(enemy, blackboard));
+    let pos = enemy.pos;
+    let time_scale = enemy.time_scale;
+    let perception = match enemy.perception {
+        Some( ref mut perception ) => perception,
+        None => return,
+    };
+
+    if sees {
+        if perception.aware {
+            perception.alert = 1.0;
+        } else {
+            let closeness = (1.0 - dist / range).max(0.0) as f32;
+            perception.alert += ALERT_GAIN * (1.0 + closeness * 2.0) * time_scale;
+        }
+        perception.last_known_pos = Some(blackboard.playerpos);
+    } else if perception.aware {
+        perception.alert -= AWARE_DECAY * time_scale;
+    } else {
+        perception.alert -= ALERT_DECAY * time_scale;
+    }
+
+    // Swings and bombs can be heard through rocks
+    match blackboard.noise {
+        Some( (noise, radius) ) => {
+            let dx = (noise.x - pos.x) as f64;
+            let dy = (noise.y - pos.y) as f64;
+            if (dx * dx + dy * dy).sqrt() < radius {
+                perception.alert = perception.alert.max(HEARD_ALERT);
+                if !sees {
+                    perception.last_known_pos = Some(noise);
+                }
+            }
+        }
+        None => {}
+    }
+
+    perception.alert = perception.alert.clamp(0.0, 1.0);
+    if perception.alert >= 1.0 && !perception.aware {
+        perception.notice();
+    }
+    if perception.alert <= 0.0 {
+        perception.aware = false;
+    }
+}
+
+// The enemy knows where the player is, e.g. it saw them for long enough or got hit
+pub fn notice(enemy: & mut Enemy){
+    match enemy.perception {
+        Some( ref mut perception ) => perception.notice(),
+        None => {}
+    }
+}
+
+// Kinds that don't have to notice the player always know where they are
+pub fn aware(enemy: &Enemy) -> bool {
+    match enemy.perception {
+        Some( ref perception ) => perception.aware,
+        None => true,
+    }
+}
+
+// Forgets about the player and anything it was looking for
+pub fn forget(enemy: & mut Enemy){
+    match enemy.perception {
+        Some( ref mut perception ) => {
+            perception.aware = false;
+            perception.alert = 0.0;
+        }
+        None => {}
+    }
+}
+
+// True if pos is inside the cone in front of the enemy
+pub fn in_vision_cone(enemy: &Enemy, pos: Vec2<f32>) -> bool {
+    let facing = match enemy.dir {
+        Direction::Up => -std::f32::consts::FRAC_PI_2,
+        Direction::Down => std::f32::consts::FRAC_PI_2,
+        Direction::Left => std::f32::consts::PI,
+        Direction::Right => 0.0,
+    };
+    let to = (pos.y - enemy.pos.y).atan2(pos.x - enemy.pos.x);
+    let mut diff = to - facing;
+    while diff > std::f32::consts::PI { diff -= std::f32::consts::PI * 2.0; }
+    while diff < -std::f32::consts::PI { diff += std::f32::consts::PI * 2.0; }
+    diff.abs() <= VISION_HALF_ANGLE
+}
+
+// Walks a loop of a few points near where the enemy started, stopping at each one
+pub fn patrol(enemy: & mut Enemy, blackboard: &BlackBoard){
+    let needs_route = match enemy.perception {
+        Some( ref perception ) => perception.patrol.is_empty(),
+        None => return,
+    };
+    if needs_route {
+        let route = make_patrol(enemy, blackboard);
+        if route.is_empty() {
+            enemy.float_in_place();
+            return;
+        }
+        set_patrol(enemy, route);
+    }
+
+    let target = match enemy.perception {
+        Some( ref mut perception ) => {
+            match perception.patrol_wait {
+                Some( time ) => {
+                    if time.elapsed().as_millis() < PATROL_PAUSE {
+                        return;
+                    }
+                    perception.patrol_wait = None;
+                    perception.patrol_index = (perception.patrol_index + 1) % perception.patrol.len();
+                }
+                None => {}
+            }
+            perception.patrol[perception.patrol_index]
+        }
+        None => return,
+    };
+    if walk_to(enemy, blackboard, target, PATROL_SPEED_MULT) {
+        set_patrol_wait(enemy, Some(Instant::now()));
+    }
+}
+
+fn set_patrol(enemy: & mut Enemy, route: Vec<Vec2<f32>>){
+    match enemy.perception {
+        Some( ref mut perception ) => perception.patrol = route,
+        None => {}
+    }
+}
+
+// Stops (Some) or starts (None) the wait at a patrol point or a searched spot
+pub fn set_patrol_wait(enemy: & mut Enemy, wait: Option<Instant>){
+    match enemy.perception {
+        Some( ref mut perception ) => perception.patrol_wait = wait,
+        None => {}
+    }
+}
+
+// Where the enemy last saw or heard the player, None if it's not looking for them
+pub fn last_known_pos(enemy: &Enemy) -> Option<Vec2<f32>> {
+    match enemy.perception {
+        Some( ref perception ) => perception.last_known_pos,
+        None => None,
+    }
+}
+
+// Gives up on looking for the player
+pub fn stop_searching(enemy: & mut Enemy){
+    match enemy.perception {
+        Some( ref mut perception ) => {
+            perception.patrol_wait = None;
+            perception.last_known_pos = None;
+        }
+        None => {}
+    }
+}
+
+// Goes to where the player was last seen or heard and looks around. Returns true once it gives up.
+pub fn search(enemy: & mut Enemy, blackboard: &BlackBoard) -> bool {
+    let target = match last_known_pos(enemy) {
+        Some( pos ) => pos,
+        None => return true,
+    };
+    let wait = match enemy.perception {
+        Some( ref perception ) => perception.patrol_wait,
+        None => return true,
+    };
+
+    match wait {
+        // Got there, turn to look around every so often
+        Some( time ) => {
+            let looked = time.elapsed().as_millis();
+            if looked >= SEARCH_LOOK_TIME {
+                stop_searching(enemy);
+                return true;
+            }
+            let turns = [Direction::Left, Direction::Up, Direction::Right, Direction::Down];
+            enemy.dir = turns[(looked / 500) as usize % turns.len()];
+        }
+        None => {
+            if walk_to(enemy, blackboard, target, SEARCH_SPEED_MULT) {
+                set_patrol_wait(enemy, Some(Instant::now()));
+            }
+        }
+    }
+    false
+}
+
+// Moves towards target at a fraction of full speed. Returns true once it's there.
+fn walk_to(enemy: & mut Enemy, blackboard: &BlackBoard, target: Vec2<f32>, speed_mult: f32) -> bool {
+    let dx = target.x - enemy.pos.x;
+    let dy = target.y - enemy.pos.y;
+    let step = (enemy.speed * speed_mult * enemy.time_scale).max(1.0);
+    if (dx * dx + dy * dy).sqrt() <= step {
+        return true;
+    }
+    enemy.pathfinding(target, blackboard);
+    enemy.pos.x += enemy.movement_vec.x * enemy.speed * speed_mult * enemy.time_scale;
+    enemy.pos.y += enemy.movement_vec.y * enemy.speed * speed_mult * enemy.time_scale;
+    false
+}
+
+// Picks a few reachable tiles around where the enemy is standing. Starts with its own spot so the
+// loop comes back around. Empty until the room's tiles are on the blackboard.
+fn make_patrol(enemy: &Enemy, blackboard: &BlackBoard) -> Vec<Vec2<f32>> {
+    if blackboard.cr_tiles.is_empty() {
+        return Vec::new();
+    }
+    let mut rng = rand::thread_rng();
+    let start = tile_of(enemy.pos);
+    let layer = enemy.layer.nav_layer();
+    let mut route = vec![enemy.pos];
+
+    for _ in 0..20 {
+        if route.len() > PATROL_POINTS {
+            break;
+        }
+        let tile = Vec2::new(
+            start.x + rng.gen_range(-PATROL_RADIUS..=PATROL_RADIUS),
+            start.y + rng.gen_range(-PATROL_RADIUS..=PATROL_RADIUS)
+        );
+        if tile == start || tile.x <= 0 || tile.y <= 0 || tile.x >= ROOM_WIDTH - 1 || tile.y >= ROOM_HEIGHT - 1 ||
+        !blackboard.is_passable(tile, layer) {
+            continue;
+        }
+        route.push(BlackBoard::tile_center(tile));
+    }
+    route
+}
@@ -1,10 +1,11 @@
 use crate::util::*;
 use crate::entity::Health;
 //use crate::boxes::*;
-use crate::boxes::Box;
+use crate::boxes::{Box, Layer};
 //use sdl2::rect::Rect;
 use std::time::{Duration, Instant};
 use crate::tile::*;
+use crate::knockback;
 
 pub enum BuffType {
     Health,
@@ -19,8 +20,25 @@ pub struct BuffInfo {
 
 pub const PLAYER_SPEED: f32 = 300.0;
 
+// Charged attack: time (ms) holding to reach a full charge, and letting go before this much
+// is just a normal swing
+pub const CHARGE_TIME: u128 = 1000;
+pub const CHARGE_MIN_TIME: u128 = 250;
+// How long (ms) the charged strike lasts once it's let go
+pub const CHARGE_STRIKE_TIME: u64 = 250;
+// Extra damage from a full charge, which also goes through shields
+pub const FULL_CHARGE_BONUS: i32 = 2;
+
+// Dodge roll: how long (ms) it lasts, how much faster than walking it goes, and the wait (ms)
+// from the start of one to the next. The player can't be hurt while rolling.
+pub const DODGE_TIME: u128 = 200;
+pub const DODGE_SPEED_MULT: f32 = 3.0;
+pub const DODGE_COOLDOWN: u128 = 600;
+
 pub struct Player {
     pub pos: Vec2<f32>, // Position of middle of player.
+    pub lastpos: Vec2<f32>, // Where this frame's move started, collision sweeps from here
+    pub knockback: Vec2<f32>, // Velocity of the shove from the last hit, zero once it's stopped
     pub pos_static: Vec2<f32>,
 
     // TODO: REWORK INTO INDIVIDUAL TRAITS SO THEY CAN BE USED WITH ENEMIES
@@ -45,14 +63,20 @@ pub struct Player {
     pub last_bomb_time: Option<Instant>,
     pub last_buff_info: Option<BuffInfo>,
     pub has_key: bool,
+    pub coins: i32,
     pub last_invincibility_time: Option<Instant>,
     pub time_between_frames: Option<Instant>,
 
 
     pub is_attacking: bool,
-    pub is_charging: bool,
+    pub is_charging: bool, // Charged strike is playing
+    pub charge_start: Option<Instant>, // When the current charge started, None if not holding one
+    pub full_charge: bool, // Last charged strike was fully charged
     pub last_attack_time: Option<Instant>,
 
+    pub last_dodge_time: Option<Instant>, // When the last roll started
+    pub dodge_dir: Vec2<f32>, // Which way the roll goes, normalised
+
     pub walkover_action: WalkoverAction,
 }
 
@@ -76,9 +100,11 @@ impl Player {
     pub fn new() -> Player {
         Player {
             pos: Vec2::new((LEFT_WALL + 8 * 64) as f32 + 32.0, (TOP_WALL + 5 * 64) as f32 + 40.0),
+            lastpos: Vec2::new((LEFT_WALL + 8 * 64) as f32 + 32.0, (TOP_WALL + 5 * 64) as f32 + 40.0),
+            knockback: Vec2::new(0.0, 0.0),
             pos_static: Vec2::new((LEFT_WALL + 8 * 64) as f32 + 32.0, (TOP_WALL + 5 * 64) as f32 + 40.0),
-            box_es: Box::new(Vec2::new(48, 52), Vec2::new(40, 24), Vec2::new(48, 64)),
-            charge_box: Box::new(Vec2::new(0, 0), Vec2::new(0, 0), Vec2::new(48, 104)),
+            box_es: Box::new(Vec2::new(48, 52), Vec2::new(40, 24), Vec2::new(48, 64)).with_layer(Layer::Player),
+            charge_box: Box::new(Vec2::new(0, 0), Vec2::new(0, 0), Vec2::new(48, 104)).with_layer(Layer::Player),
             speed: PLAYER_SPEED,
             stored_speed: PLAYER_SPEED,
             dir: Direction::Down,
@@ -101,20 +127,27 @@ impl Player {
             last_bomb_time: None,
             last_buff_info: None,
             has_key: false,
+            coins: 0,
             last_invincibility_time: None,
             time_between_frames: None,
 
             //timing attacks so they aren't just 'on'
             is_attacking: false,
             is_charging: false,
+            charge_start: None,
+            full_charge: false,
             last_attack_time: Some(Instant::now()),
 
+            last_dodge_time: None,
+            dodge_dir: Vec2::new(0.0, 0.0),
+
             walkover_action: WalkoverAction::DoNothing,
 
         }
     }
 
     pub fn update_pos(& mut self, mut mov_vec: Vec2<f32>) {
+        self.lastpos = self.pos;
 
         // Fix diagonal directions giving more speed than one direction
         if mov_vec.x != 0.0 && mov_vec.y != 0.0 {
@@ -138,8 +171,15 @@ impl Player {
                 // still allowing player movement to occur, but delaying it slightly until
                 // the room transition is essentially finished.
                 if val < 300.0 {
+                    // Rolling takes over from walking until it's done
+                    if self.dodging() {
+                        mov_vec.x = self.dodge_dir.x * DODGE_SPEED_MULT;
+                        mov_vec.y = self.dodge_dir.y * DODGE_SPEED_MULT;
+                    }
                     self.pos.x += mov_vec.x * self.speed * ( val / 1000.0 );
                     self.pos.y += mov_vec.y * self.speed * ( val / 1000.0 );
+                    // Knockback speeds are per 60fps frame like the enemies'
+                    knockback::step(&mut self.knockback, &mut self.pos, val / 16.6);
                 }
             },
             None => {}
@@ -174,15 +214,102 @@ impl Player {
         self.pos_static = self.pos;
     }
 
+    // Starts a roll the way the player is moving, or the way they're facing if they're standing
+    // still. Does nothing while the last one is cooling down or while attacking.
+    pub fn dodge(&mut self, mov_vec: Vec2<f32>) {
+        match self.last_dodge_time {
+            Some( time ) if time.elapsed().as_millis() < DODGE_COOLDOWN => return,
+            _ => {}
+        }
+        if self.is_attacking || self.is_charging || self.holding_charge() {
+            return;
+        }
+        let length = (mov_vec.x * mov_vec.x + mov_vec.y * mov_vec.y).sqrt();
+        self.dodge_dir = if length > 0.0 {
+            Vec2::new(mov_vec.x / length, mov_vec.y / length)
+        }
+        else {
+            match self.dir {
+                Direction::Up => Vec2::new(0.0, -1.0),
+                Direction::Down => Vec2::new(0.0, 1.0),
+                Direction::Left => Vec2::new(-1.0, 0.0),
+                Direction::Right => Vec2::new(1.0, 0.0),
+            }
+        };
+        self.last_dodge_time = Some(Instant::now());
+        // A roll gets the player out of a shove too
+        self.knockback = Vec2::new(0.0, 0.0);
+    }
+
+    pub fn dodging(&self) -> bool {
+        match self.last_dodge_time {
+            Some( time ) => time.elapsed().as_millis() < DODGE_TIME,
+            None => false,
+        }
+    }
+
+    // Where the current roll will end up if nothing stops it, for enemies leading their aim
+    pub fn dodge_end(&self) -> Vec2<f32> {
+        let left = match self.last_dodge_time {
+            Some( time ) => DODGE_TIME.saturating_sub(time.elapsed().as_millis()) as f32,
+            None => 0.0,
+        };
+        let distance = self.speed * DODGE_SPEED_MULT * left / 1000.0;
+        Vec2::new(self.pos.x + self.dodge_dir.x * distance, self.pos.y + self.dodge_dir.y * distance)
+    }
+
+    // Starts holding a charge, the strike happens on release_charge
+    pub fn start_charge(&mut self) {
+        self.charge_start = Some(Instant::now());
+    }
+
+    pub fn holding_charge(&self) -> bool {
+        self.charge_start.is_some()
+    }
+
+    // How far along the charge being held is, from 0.0 to 1.0
+    pub fn charge_progress(&self) -> f32 {
+        match self.charge_start {
+            Some( time ) => (time.elapsed().as_millis() as f32 / CHARGE_TIME as f32).min(1.0),
+            None => 0.0,
+        }
+    }
+
+    // Lets go of the charge. Too short a hold is a normal swing.
+    pub fn release_charge(&mut self) {
+        let held = match self.charge_start {
+            Some( time ) => time.elapsed().as_millis(),
+            None => return,
+        };
+        self.charge_start = None;
+        if held < CHARGE_MIN_TIME {
+            self.signal_attack();
+        }
+        else {
+            self.full_charge = held >= CHARGE_TIME;
+            self.signal_charge();
+        }
+    }
+
     pub fn signal_charge(&mut self) {
         self.is_charging = true;
         self.last_attack_time = Some(Instant::now());
     }
 
+    // Damage of the swing currently out, full charges hit harder
+    pub fn swing_damage(&self) -> i32 {
+        if self.is_charging && self.full_charge {
+            self.attack + FULL_CHARGE_BONUS
+        }
+        else {
+            self.attack
+        }
+    }
+
     pub fn recently_charged(&mut self) -> bool {
         match self.last_attack_time {
             Some( time ) => {
-                let res = time.elapsed() <= Duration::from_millis(1000);
+                let res = time.elapsed() <= Duration::from_millis(CHARGE_STRIKE_TIME);
                 if !res {
                     self.is_charging = false;
                 }
@@ -270,6 +397,10 @@ impl Player {
     }
 
     pub fn take_damage(&mut self, amount: i32, cooldown_window_ms: u64) {
+        // Rolling is invincible
+        if self.dodging() {
+            return;
+        }
         match self.last_invincibility_time {
             // If there is an old invincibility time for the player,
             // see if the "invincibility window" has elapsed since then...
@@ -289,6 +420,17 @@ impl Player {
         }
     }
 
+    // take_damage that also shoves the player away from from. Returns true if the hit landed.
+    pub fn take_hit(&mut self, amount: i32, cooldown_window_ms: u64, from: Vec2<f32>, force: f32) -> bool {
+        let before = self.last_invincibility_time;
+        self.take_damage(amount, cooldown_window_ms);
+        if self.last_invincibility_time == before {
+            return false;
+        }
+        self.knockback = knockback::push_from(from, self.pos, force);
+        true
+    }
+
     pub fn use_bomb(&mut self) {
         self.update_static_pos();
         self.has_bomb = false;
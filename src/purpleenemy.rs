@@ -0,0 +1,120 @@
+use crate::blackboard::*;
+use crate::util::*;
+use crate::entity::*;
+use crate::tile::*;
+use crate::behavior::*;
+use std::time::Instant;
+
+// Flying enemy. Swoops in for a hit then hangs over a pit or rock where the player can't follow.
+
+// How long (ms) it hangs back after a swoop before diving in again
+pub const FLYING_RETREAT_TIME: u128 = 1200;
+// How many tiles away it will look for a pit or rock to hover over
+pub const FLYING_ROOST_RADIUS: i32 = 4;
+
+pub struct PurpleBehavior;
+
+impl EnemyBehavior for PurpleBehavior {
+    fn flee(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        flee_straight(enemy, blackboard);
+    }
+
+    fn attack(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if dodge_charge(enemy, blackboard, false) {
+            return;
+        }
+
+        enemy.signal_attack();
+
+        // Hit and run, back off as soon as the swoop lands
+        if enemy.is_attacking {
+            enemy.cover_tile = None;
+            enemy.state_timer = Instant::now();
+            enemy.state = State::Retreat;
+            return;
+        }
+
+        if !Enemy::player_close(enemy, blackboard){
+            enemy.state = State::Chase;
+        }
+    }
+
+    fn retreat(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+
+        // Hover over a pit or rock so the player has to wait for the next swoop
+        if enemy.cover_tile.is_none() {
+            enemy.cover_tile = find_roost(enemy, blackboard);
+        }
+
+        match enemy.cover_tile {
+            Some( tile ) => {
+                enemy.move_towards(BlackBoard::tile_center(tile), blackboard);
+            }
+            None => {
+                self.flee(enemy, blackboard);
+            }
+        }
+
+        if enemy.state_timer.elapsed().as_millis() >= FLYING_RETREAT_TIME {
+            enemy.cover_tile = None;
+            enemy.state = State::Chase;
+        }
+    }
+
+    fn chase(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if dodge_charge(enemy, blackboard, false) {
+            return;
+        }
+
+        // Uses the flying flow field, so it cuts straight over pits and rocks
+        enemy.move_towards(blackboard.playerpos, blackboard);
+
+        if Enemy::player_close(enemy, blackboard)
+        {
+            enemy.state = State::Attack;
+        }
+    }
+
+    fn heal(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        // Flyers don't go looking for health enemies
+        enemy.is_healing = false;
+        enemy.state = State::Chase;
+    }
+
+    // Sees further than the slimes do from up high
+    fn sight_range(&self) -> f64 { 400.0 }
+}
+
+// Closest pit or rock that isn't right next to the player
+fn find_roost(enemy: &Enemy, blackboard: &BlackBoard) -> Option<Vec2<i32>> {
+    if blackboard.cr_tiles.is_empty() {
+        return None;
+    }
+
+    let mut best: Option<(Vec2<i32>, i32)> = None;
+    for dy in -FLYING_ROOST_RADIUS..=FLYING_ROOST_RADIUS {
+        for dx in -FLYING_ROOST_RADIUS..=FLYING_ROOST_RADIUS {
+            let tile = Vec2::new(enemy.current_frame_tile.x + dx, enemy.current_frame_tile.y + dy);
+            if tile.x < 0 || tile.x >= blackboard.cr_tiles[0].len() as i32 || tile.y < 0 || tile.y >= blackboard.cr_tiles.len() as i32 {
+                continue;
+            }
+            let near_player = (tile.x - blackboard.player_frame_tile.x).abs() <= 1 &&
+                (tile.y - blackboard.player_frame_tile.y).abs() <= 1;
+            if near_player {
+                continue;
+            }
+            match blackboard.cr_tiles[tile.y as usize][tile.x as usize].walkability() {
+                Walkability::Pit | Walkability::Rock => {
+                    let dist = dx.abs() + dy.abs();
+                    match best {
+                        Some((_, best_dist)) if best_dist <= dist => {}
+                        _ => best = Some((tile, dist)),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    best.map(|(tile, _)| tile)
+}
@@ -1,17 +1,25 @@
 use crate::util::*;
 use crate::tile::*;
 use crate::entity::*;
+use crate::arena::*;
+use crate::world::World;
+use crate::navigation::tile_of;
 use rand::Rng;
+use std::collections::VecDeque;
 
 pub const ROOM_WIDTH: i32 = 17;
 pub const ROOM_HEIGHT: i32 = 11;
+// Crates are kept at least this many tiles (across or down) away from doors and the trapdoor
+pub const CRATE_EXIT_GAP: i32 = 2;
 
 
 pub struct Room {
     pub exists: bool,
     pub visited: bool,
+    pub directed: bool, // The difficulty director has already adjusted this room's enemies
     pub tiles: Vec<Vec<Box<dyn Tile>>>,
-    pub enemies: Vec<Enemy>,
+    pub enemies: Arena<Enemy>, // Handles to these stay valid while the enemy is in the room
+    pub props: World, // Crates and other simple things built out of components
     pub gem_count: i32,
 }
 
@@ -28,7 +36,7 @@ pub struct Room {
 impl Room {
     // Returns a room that the developer sets every tile of manually.
     pub fn non_room() -> Room {
-        Room { exists: false, visited: false, tiles: Vec::new(), enemies: Vec::new(), gem_count: 0 }
+        Room { exists: false, visited: false, directed: false, tiles: Vec::new(), enemies: Arena::new(), props: World::new(), gem_count: 0 }
 
     }
     pub fn new_test_room(blueprint: [[char; 17]; 11]) -> Room {
@@ -72,7 +80,7 @@ impl Room {
                     'P' => tiles[y as usize].push(Box::new(Pit {})),
                     'S' => tiles[y as usize].push(Box::new(Spike { gem: Gem::None })),
                     // TODO: Add code for proper handling of direction
-                    'D' => tiles[y as usize].push(Box::new(Door { lock: LockState::Unlocked, position: Direction::Up })),
+                    'D' => tiles[y as usize].push(Box::new(Door { lock: LockState::Unlocked })),
 
                     'K' => tiles[y as usize].push(Box::new(Key { has_key: true, })),
                     'T' => tiles[y as usize].push(Box::new(Trapdoor { lock: LockState::Locked })),
@@ -110,8 +118,10 @@ impl Room {
         Room {
             exists: true,
             visited: false,
+            directed: false,
             tiles: tiles,
-            enemies: Vec::new(),
+            enemies: Arena::new(),
+            props: World::new(),
             gem_count: 0,
         }
     }
@@ -120,20 +130,66 @@ impl Room {
         &mut self.tiles[ ((y - TOP_WALL) / 64) as usize ][ ((x - LEFT_WALL) / 64) as usize ]
     }
 
+    // Whether a projectile at this position should be stopped by the tile under it.
+    // Anything outside of the tile grid counts as a wall.
+    pub fn blocks_projectile(&self, pos: Vec2<f32>) -> bool {
+        let x = (pos.x as i32 - LEFT_WALL).div_euclid(TILE_WIDTH);
+        let y = (pos.y as i32 - TOP_WALL).div_euclid(TILE_WIDTH);
+        if !(0..ROOM_WIDTH).contains(&x) || !(0..ROOM_HEIGHT).contains(&y) {
+            return true;
+        }
+        matches!(self.tiles[y as usize][x as usize].walkability(), Walkability::Wall | Walkability::Rock) ||
+            self.crate_on_tile(Vec2::new(x, y))
+    }
+
+    // True if a crate (or any other solid prop) is standing on tile
+    pub fn crate_on_tile(&self, tile: Vec2<i32>) -> bool {
+        self.props.solid_tiles().contains(&tile)
+    }
+
     /*pub fn tile_at_vec2(&mut self, pos: Vec2<i32>) -> &mut Box<dyn Tile> {
         self.tile_at( pos.x, pos.y )
     }*/
 
     pub fn add_enemies(&mut self, enemies: Vec<Enemy>) {
-        self.enemies = enemies;
+        self.enemies = Arena::from(enemies);
     }
 
     pub fn increment_gem(&mut self){
         self.gem_count += 1
     }
 
-    pub fn additional_enemies(&mut self, enemy: Enemy) {
-        self.enemies.push(enemy);
+    pub fn additional_enemies(&mut self, enemy: Enemy) -> Handle {
+        self.enemies.insert(enemy)
+    }
+
+    // Adds every enemy's summoned minions and split-offs to the room, summoners keep track of theirs,
+    // and drops minions that have died from their summoner's list
+    pub fn add_pending_spawns(&mut self) {
+        for parent in self.enemies.handles() {
+            let spawned = std::mem::take(&mut self.enemies[parent].final_enemies_to_spawn);
+            for child in spawned {
+                let handle = self.additional_enemies(child);
+                match self.enemies[parent].minions {
+                    Some( ref mut minions ) => minions.push(handle),
+                    None => {}
+                }
+            }
+        }
+
+        for handle in self.enemies.handles() {
+            let minions = match self.enemies[handle].minions.take() {
+                Some( minions ) => minions,
+                None => continue,
+            };
+            let alive = minions.into_iter()
+                .filter(|m| match self.enemies.get(*m) {
+                    Some( minion ) => !minion.death,
+                    None => false,
+                })
+                .collect();
+            self.enemies[handle].minions = Some(alive);
+        }
     }
 
     // Repositions enemies to be a certain distance from player
@@ -143,18 +199,19 @@ impl Room {
         //println!("Repositioning enemies...");
 
         let mut rng = rand::thread_rng();
+        let crates = self.props.solid_tiles();
 
-        for mut enemy in &mut self.enemies {
+        for enemy in self.enemies.iter_mut() {
 
             loop {
                 // Get distance between player and enemy
                 let dist = ((enemy.pos.x - player_pos.x).powf(2.0) + (enemy.pos.y - player_pos.y).powf(2.0)).sqrt();
 
-                // See if tile enemy is on is walkable
+                // See if tile enemy is on is walkable, and not under a crate
                 let valid_tile = self.tiles
                     [((enemy.pos.y as i32 - TOP_WALL) / 64) as usize]
                     [((enemy.pos.x as i32 - LEFT_WALL) / 64) as usize]
-                    .walkability() == Walkability::Floor;
+                    .walkability() == Walkability::Floor && !crates.contains(&tile_of(enemy.pos));
 
                 // Reroll enemy position if too close or not walkable
                 if dist < 250.0 || !valid_tile {
@@ -169,77 +226,106 @@ impl Room {
         }
     }
 
-    // Removes enemies when the player is at low hp
-    pub fn ease_enemy_difficulty(&mut self, hp: i32) {
-        
-        //for mut enemy in &mut self.enemies { enemy.is_ranged = false; }
-        
-        match hp {
-
-            // When at 1 heart, get rid of yellow enemies to avoid player getting one shot
-            2 => {
-                self.enemies.retain(
-                    |enemy| {
-                        enemy.kind == EnemyKind::Health || enemy.kind == EnemyKind::Speed
-                    }
-                );
+    // A free ground tile next to tile (in tile coordinates), for dropping pickups. Never picks avoid.
+    // None if it's boxed in.
+    pub fn free_tile_near(&self, tile: Vec2<i32>, avoid: Option<Vec2<i32>>) -> Option<Vec2<i32>> {
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, -1), (1, -1), (-1, 1)] {
+            let x = tile.x + dx;
+            let y = tile.y + dy;
+            if x <= 0 || y <= 0 || x >= ROOM_WIDTH - 1 || y >= ROOM_HEIGHT - 1 || avoid == Some(Vec2::new(x, y)) {
+                continue;
             }
+            let t = &self.tiles[y as usize][x as usize];
+            if t.walkability() == Walkability::Floor && t.sprite() == SpriteID::Ground && !t.has_gem() {
+                return Some(Vec2::new(x, y));
+            }
+        }
+        None
+    }
 
-            // When at half a heart, only spawn 1 enemy red enemy.
-            1 => {
-                self.enemies.clear();
-                let mut rng = rand::thread_rng();
-                let choice = rng.gen_range(0..=3);
-                let mut x:f32;
-                let mut y:f32;
-                match choice {
-                    0 => {
-                        x = rng.gen_range(LEFT_WALL..RIGHT_WALL) as f32;
-                        y = rng.gen_range(TOP_WALL..BOT_WALL) as f32;
-                        self.enemies.push(Enemy::new(Vec2 {x, y}, EnemyKind::Health));
-                    }
-                    1 => {
-                        x = rng.gen_range(LEFT_WALL..RIGHT_WALL) as f32;
-                        y = rng.gen_range(TOP_WALL..BOT_WALL) as f32;
-                        self.enemies.push(Enemy::new(Vec2 {x, y}, EnemyKind::Health));
-                        x = rng.gen_range(LEFT_WALL..RIGHT_WALL) as f32;
-                        y = rng.gen_range(TOP_WALL..BOT_WALL) as f32;
-                        self.enemies.push(Enemy::new(Vec2 {x, y}, EnemyKind::Health));
-
-                    }
-                    2 => {
-                        x = rng.gen_range(LEFT_WALL..RIGHT_WALL) as f32;
-                        y = rng.gen_range(TOP_WALL..BOT_WALL) as f32;
-                        self.enemies.push(Enemy::new(Vec2 {x, y}, EnemyKind::Health));
-                        x = rng.gen_range(LEFT_WALL..RIGHT_WALL) as f32;
-                        y = rng.gen_range(TOP_WALL..BOT_WALL) as f32;
-                        self.enemies.push(Enemy::new(Vec2 {x, y}, EnemyKind::Speed));
-                    }
-                    3 => {
-                        x = rng.gen_range(LEFT_WALL..RIGHT_WALL) as f32;
-                        y = rng.gen_range(TOP_WALL..BOT_WALL) as f32;
-                        self.enemies.push(Enemy::new(Vec2 {x, y}, EnemyKind::Health));
-                        x = rng.gen_range(LEFT_WALL..RIGHT_WALL) as f32;
-                        y = rng.gen_range(TOP_WALL..BOT_WALL) as f32;
-                        self.enemies.push(Enemy::new(Vec2 {x, y}, EnemyKind::Attack));
-
-                    }
-                    _ => {}
+    // A random ground tile (in tile coordinates) at least min_dist tiles from avoid, for putting new
+    // enemies down. None if there isn't one.
+    pub fn open_tile_away_from(&self, avoid: Vec2<i32>, min_dist: i32) -> Option<Vec2<i32>> {
+        let mut open = Vec::new();
+        for y in 1..ROOM_HEIGHT - 1 {
+            for x in 1..ROOM_WIDTH - 1 {
+                let (dx, dy) = (x - avoid.x, y - avoid.y);
+                if dx * dx + dy * dy < min_dist * min_dist {
+                    continue;
+                }
+                if self.tiles[y as usize][x as usize].walkability() == Walkability::Floor && !self.crate_on_tile(Vec2::new(x, y)) {
+                    open.push(Vec2::new(x, y));
                 }
             }
+        }
+        if open.is_empty() {
+            return None;
+        }
+        Some(open[rand::thread_rng().gen_range(0..open.len())])
+    }
 
-            _ => {}
+    // Doors and the trapdoor, every tile the player can leave the room through
+    pub fn exits(&self) -> Vec<Vec2<i32>> {
+        let mut exits = Vec::new();
+        for y in 0..ROOM_HEIGHT {
+            for x in 0..ROOM_WIDTH {
+                if self.tiles[y as usize][x as usize].get_lock_state() != LockState::NA {
+                    exits.push(Vec2::new(x, y));
+                }
+            }
         }
-        // When at lower than 1.5 hearts, don't spawn ranged enemies
-        if hp <= 3 {
-            for mut enemy in &mut self.enemies { enemy.is_ranged = false; }
+        exits
+    }
+
+    // Whether a crate can go on tile: open ground with nothing on it, not in front of a way out, and
+    // not cutting off a way out that could be walked to before
+    pub fn crate_fits(&self, tile: Vec2<i32>) -> bool {
+        if self.tiles[tile.y as usize][tile.x as usize].walkability() != Walkability::Floor || self.crate_on_tile(tile) ||
+        self.enemies.iter().any(|e| tile_of(e.pos) == tile) {
+            return false;
+        }
+        let exits = self.exits();
+        if exits.iter().any(|e| (e.x - tile.x).abs() <= CRATE_EXIT_GAP && (e.y - tile.y).abs() <= CRATE_EXIT_GAP) {
+            return false;
         }
+        let mut blocked = self.props.solid_tiles();
+        let before = self.reachable_exits(&exits, &blocked);
+        blocked.push(tile);
+        self.reachable_exits(&exits, &blocked) == before
+    }
 
+    // How many exits can be walked to from the first one without going through a blocked tile.
+    // Exits count as open even while they're locked.
+    fn reachable_exits(&self, exits: &[Vec2<i32>], blocked: &[Vec2<i32>]) -> usize {
+        let first = match exits.first() {
+            Some( first ) => *first,
+            None => return 0,
+        };
+        let mut seen = vec![vec![false; ROOM_WIDTH as usize]; ROOM_HEIGHT as usize];
+        let mut queue = VecDeque::new();
+        seen[first.y as usize][first.x as usize] = true;
+        queue.push_back(first);
+        while let Some( tile ) = queue.pop_front() {
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let next = Vec2::new(tile.x + dx, tile.y + dy);
+                if !(0..ROOM_WIDTH).contains(&next.x) || !(0..ROOM_HEIGHT).contains(&next.y) || seen[next.y as usize][next.x as usize] {
+                    continue;
+                }
+                let t = &self.tiles[next.y as usize][next.x as usize];
+                let exit = t.get_lock_state() != LockState::NA;
+                if blocked.contains(&next) || (!exit && MovementLayer::Walker.blocked_by(&t.walkability())) {
+                    continue;
+                }
+                seen[next.y as usize][next.x as usize] = true;
+                queue.push_back(next);
+            }
+        }
+        exits.iter().filter(|e| seen[e.y as usize][e.x as usize]).count()
     }
 
     // Provides enemies with time_scale so they can do frame independent movement
     pub fn update_enemies(&mut self, ts: f32) {
-        for mut x in &mut self.enemies {
+        for x in self.enemies.iter_mut() {
             x.time_scale = ts;
         }
     }
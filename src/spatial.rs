@@ -0,0 +1,63 @@
+use crate::util::*;
+use crate::room::{ROOM_WIDTH, ROOM_HEIGHT};
+use sdl2::rect::Rect;
+
+// Broad phase for collision checks. A uniform grid laid over the room with one cell per tile, so a
+// box only has to be tested against the things sharing its cells instead of everything in the room.
+// Projectile against tile checks don't need it, the tile grid already is one (Room::blocks_projectile).
+// Anything outside the room is kept in the nearest edge cell so it can still be found.
+
+pub struct SpatialGrid {
+    cells: Vec<Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new() -> SpatialGrid {
+        SpatialGrid {
+            cells: vec![Vec::new(); (ROOM_WIDTH * ROOM_HEIGHT) as usize],
+        }
+    }
+
+    // Adds id to every cell rect touches. id is whatever the caller uses to find the thing again,
+    // usually its index in a list.
+    pub fn insert(&mut self, id: usize, rect: Rect) {
+        let (x0, y0, x1, y1) = cell_range(rect);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.cells[(y * ROOM_WIDTH + x) as usize].push(id);
+            }
+        }
+    }
+
+    // Everything sharing a cell with rect, each id once and in order. These still need a real
+    // intersection test, they're only close enough to bother checking.
+    pub fn query(&self, rect: Rect) -> Vec<usize> {
+        let (x0, y0, x1, y1) = cell_range(rect);
+        let mut found = Vec::new();
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                found.extend_from_slice(&self.cells[(y * ROOM_WIDTH + x) as usize]);
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+// Square of side size centred on pos, for things that are spaced by distance rather than a box
+pub fn square_at(pos: Vec2<f32>, size: f32) -> Rect {
+    let size = size.max(1.0);
+    Rect::new((pos.x - size / 2.0) as i32, (pos.y - size / 2.0) as i32, size as u32, size as u32)
+}
+
+// Cells (x0, y0, x1, y1) a rect covers, clamped to the room
+fn cell_range(rect: Rect) -> (i32, i32, i32, i32) {
+    let cell = |px: i32, offset: i32, max: i32| ((px - offset).div_euclid(TILE_WIDTH)).clamp(0, max - 1);
+    (
+        cell(rect.left(), LEFT_WALL, ROOM_WIDTH),
+        cell(rect.top(), TOP_WALL, ROOM_HEIGHT),
+        cell(rect.right() - 1, LEFT_WALL, ROOM_WIDTH),
+        cell(rect.bottom() - 1, TOP_WALL, ROOM_HEIGHT),
+    )
+}
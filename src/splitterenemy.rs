@@ -0,0 +1,22 @@
+use crate::blackboard::*;
+use crate::entity::*;
+use crate::behavior::*;
+
+// Splitter. A big slow blob that breaks into splitlings when it dies (see splits_into in
+// assets/enemies.txt). Splitlings use the same behaviour, they're just small and quick.
+
+pub struct SplitterBehavior;
+
+impl EnemyBehavior for SplitterBehavior {
+    // Too slow to make it to a red enemy, and the pieces don't live long enough to bother
+    fn seeks_healing(&self) -> bool { false }
+
+    // Stands its ground against a charge, it's meant to be hit
+    fn take_cover(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.state = State::Chase;
+    }
+
+    fn retreat(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.state = State::Chase;
+    }
+}
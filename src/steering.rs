@@ -0,0 +1,117 @@
+use crate::util::*;
+use crate::entity::*;
+use crate::blackboard::*;
+use crate::navigation::*;
+use crate::spatial::*;
+use crate::arena::Arena;
+
+// Crowd steering run after every enemy has updated. Enemies push away from each other (and out of
+// the player's body) so groups spread out around the player instead of stacking up.
+
+// How hard (px per frame) two fully overlapping enemies push apart
+pub const SEPARATION_STRENGTH: f32 = 2.0;
+// Red enemies only keep this fraction of the normal spacing so they bunch up and heal each other
+pub const HEALER_CLUSTER_SPACING: f32 = 0.5;
+// Fraction of the touching distance enemies can come into the player. Melee enemies need their
+// walkbox to overlap the player's to attack so this has to stay under 1.0.
+pub const PLAYER_BODY_SPACING: f32 = 0.6;
+
+// Distance at which two boxes centred on each other would just touch, using the larger side
+fn body_size(enemy: &Enemy) -> f32 {
+    enemy.box_es.walkbox.x.max(enemy.box_es.walkbox.y) as f32
+}
+
+// Push needed to move a away from b, zero if they are already far enough apart
+fn push_apart(a: Vec2<f32>, b: Vec2<f32>, min_dist: f32) -> Vec2<f32> {
+    let vector = Vec2::new(a.x - b.x, a.y - b.y);
+    let length = (vector.x * vector.x + vector.y * vector.y).sqrt();
+    if length >= min_dist || min_dist <= 0.0 {
+        return Vec2::new(0.0, 0.0);
+    }
+
+    // Stacked exactly on top of each other, pick a side so they still split
+    let (dir_x, dir_y) = if length < 0.01 { (1.0, 0.0) } else { (vector.x / length, vector.y / length) };
+    let strength = (min_dist - length) / min_dist * SEPARATION_STRENGTH;
+    Vec2::new(dir_x * strength, dir_y * strength)
+}
+
+// How much of the normal spacing a pair of enemies keeps. 0.0 means they are allowed to overlap.
+fn pair_spacing(a: &Enemy, b: &Enemy) -> f32 {
+    // Going to a red enemy for healing needs the hitboxes to touch
+    if (a.state == State::Heal && b.kind == EnemyKind::Health) ||
+    (b.state == State::Heal && a.kind == EnemyKind::Health) {
+        return 0.0;
+    }
+    if a.kind == EnemyKind::Health && b.kind == EnemyKind::Health {
+        return HEALER_CLUSTER_SPACING;
+    }
+    1.0
+}
+
+pub fn separate(enemies: &mut Arena<Enemy>, blackboard: &BlackBoard) {
+    // Work out every push from where everyone is now, then apply, so update order doesn't matter
+    // Grid ids and pushes line up with this list
+    let handles = enemies.handles();
+    let mut pushes = vec![Vec2::new(0.0, 0.0); handles.len()];
+
+    // Only enemies whose bodies share a cell can be close enough to push each other
+    let mut grid = SpatialGrid::new();
+    for (i, handle) in handles.iter().enumerate() {
+        let enemy = &enemies[*handle];
+        if !enemy.death {
+            grid.insert(i, square_at(enemy.pos, body_size(enemy)));
+        }
+    }
+
+    for i in 0..handles.len() {
+        let enemy = &enemies[handles[i]];
+        // The boss holds its ground, everything else moves around it
+        if enemy.death || enemy.kind == EnemyKind::Final {
+            continue;
+        }
+
+        for j in grid.query(square_at(enemy.pos, body_size(enemy))) {
+            let other = &enemies[handles[j]];
+            if i == j {
+                continue;
+            }
+            let min_dist = (body_size(enemy) + body_size(other)) / 2.0 * pair_spacing(enemy, other);
+            let push = push_apart(enemy.pos, other.pos, min_dist);
+            pushes[i].x += push.x;
+            pushes[i].y += push.y;
+        }
+
+        let player_size = blackboard.player_box.walkbox.x.max(blackboard.player_box.walkbox.y) as f32;
+        let min_dist = (body_size(enemy) + player_size) / 2.0 * PLAYER_BODY_SPACING;
+        let push = push_apart(enemy.pos, blackboard.playerpos, min_dist);
+        pushes[i].x += push.x;
+        pushes[i].y += push.y;
+    }
+
+    for (handle, push) in handles.iter().zip(pushes.iter()) {
+        let enemy = &mut enemies[*handle];
+        if push.x == 0.0 && push.y == 0.0 {
+            continue;
+        }
+        let push = Vec2::new(push.x * enemy.time_scale, push.y * enemy.time_scale);
+
+        // Move one axis at a time so an enemy pushed at a rock still slides along it
+        let next_x = Vec2::new(enemy.pos.x + push.x, enemy.pos.y);
+        if can_stand(enemy, next_x, blackboard) {
+            enemy.pos.x = next_x.x;
+        }
+        let next_y = Vec2::new(enemy.pos.x, enemy.pos.y + push.y);
+        if can_stand(enemy, next_y, blackboard) {
+            enemy.pos.y = next_y.y;
+        }
+    }
+}
+
+// Separation shouldn't shove anyone onto a tile their layer can't be on
+fn can_stand(enemy: &Enemy, pos: Vec2<f32>, blackboard: &BlackBoard) -> bool {
+    if blackboard.cr_tiles.is_empty() {
+        return true;
+    }
+    let tile = tile_of(pos);
+    in_bounds(tile) && blackboard.is_passable(tile, enemy.layer)
+}
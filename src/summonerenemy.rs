@@ -0,0 +1,88 @@
+use crate::blackboard::*;
+use crate::entity::*;
+use crate::enemydef::*;
+use crate::behavior::*;
+use std::time::Instant;
+
+// Summoner. Keeps its distance from the player and calls in minions, the same way the boss does,
+// so it's the one to go after first.
+
+// Backs off when the player is closer than this (px)
+pub const SUMMONER_NEAR: f64 = 200.0;
+// Walks closer when the player is further than this (px)
+pub const SUMMONER_FAR: f64 = 350.0;
+// Time (ms) between summons
+pub const SUMMONER_COOLDOWN: u128 = 5000;
+// Telegraph length (ms) before the minions show up
+pub const SUMMONER_WINDUP_TIME: u128 = 800;
+// Won't summon if the room already has this many enemies
+pub const SUMMONER_ROOM_CAP: i32 = 6;
+// Won't summon while this many of its own minions are still alive
+pub const SUMMONER_MINION_CAP: usize = 3;
+// How far (px) from the summoner the minions appear
+pub const SUMMONER_SPAWN_RADIUS: f32 = 40.0;
+
+pub struct SummonerBehavior;
+
+impl EnemyBehavior for SummonerBehavior {
+    fn seeks_healing(&self) -> bool { false }
+
+    fn flee(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        flee_straight(enemy, blackboard);
+    }
+
+    // Attack is the summon, it stands still while the spell winds up
+    fn attack(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.telegraph = true;
+        if enemy.state_timer.elapsed().as_millis() < SUMMONER_WINDUP_TIME {
+            return;
+        }
+
+        match enemy_def(enemy.kind).summons {
+            Some( (kind, count) ) => enemy.queue_spawns(kind, count, SUMMONER_SPAWN_RADIUS),
+            None => {}
+        }
+        enemy.telegraph = false;
+        enemy.last_shot_time = Some(Instant::now());
+        enemy.state = State::Chase;
+    }
+
+    fn chase(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if dodge_charge(enemy, blackboard, false) {
+            return;
+        }
+
+        let distance = Enemy::distance_to_player(enemy, blackboard);
+        if distance < SUMMONER_NEAR {
+            self.flee(enemy, blackboard);
+        } else if distance > SUMMONER_FAR {
+            enemy.move_towards(blackboard.playerpos, blackboard);
+        }
+
+        if summon_ready(enemy) && blackboard.enemy_quantity < SUMMONER_ROOM_CAP && minion_count(enemy) < SUMMONER_MINION_CAP {
+            enemy.state_timer = Instant::now();
+            enemy.state = State::Attack;
+            return;
+        }
+
+        low_hp_transitions(enemy, blackboard, false, self.seeks_healing());
+    }
+
+    fn sight_range(&self) -> f64 { 400.0 }
+}
+
+// Uses last_shot_time as the summon cooldown since it never shoots
+fn summon_ready(enemy: &Enemy) -> bool {
+    match enemy.last_shot_time {
+        Some( time ) => time.elapsed().as_millis() >= SUMMONER_COOLDOWN,
+        None => true,
+    }
+}
+
+// How many of its own minions are still alive
+fn minion_count(enemy: &Enemy) -> usize {
+    match enemy.minions {
+        Some( ref minions ) => minions.len(),
+        None => 0,
+    }
+}
@@ -55,6 +55,8 @@ pub enum WalkoverAction {
     BuffHealth,
     BuffDamage,
     BuffSpeed,
+    Heal(i32),
+    GiveCoin,
 }
 
 pub struct Ground {
@@ -205,7 +207,6 @@ impl Tile for Spike {
 
 pub struct Door {
     pub(crate) lock: LockState,
-    pub(crate) position: Direction,
 }
 impl Tile for Door {
     fn sprite(&self) -> SpriteID {
@@ -279,6 +280,46 @@ impl Tile for Bomb {
     }
 }
 
+// Loot dropped by an enemy or for clearing a room
+pub struct Pickup {
+    pub(crate) loot: Loot,
+}
+impl Tile for Pickup {
+    fn sprite(&self) -> SpriteID {
+        match self.loot {
+            Loot::HalfHeart => SpriteID::HalfHeart,
+            Loot::Heart => SpriteID::Heart,
+            Loot::Bomb => SpriteID::Bomb,
+            Loot::Coin => SpriteID::Coin,
+            Loot::Nothing => SpriteID::Ground,
+        }
+    }
+    fn walkability(&self) -> Walkability { Walkability::Floor }
+    fn on_walkover(& mut self) -> WalkoverAction {
+        let action = match self.loot {
+            Loot::HalfHeart => WalkoverAction::Heal(1),
+            Loot::Heart => WalkoverAction::Heal(2),
+            Loot::Bomb => WalkoverAction::GivePlayerBomb,
+            Loot::Coin => WalkoverAction::GiveCoin,
+            Loot::Nothing => WalkoverAction::DoNothing,
+        };
+        self.loot = Loot::Nothing;
+        action
+    }
+
+    fn lock(& mut self) {}
+    fn unlock(& mut self) {}
+    fn explode(& mut self) {}
+    fn get_lock_state(&self) -> LockState { LockState::NA }
+    fn place_gem(&mut self, _color: Gem) {}
+    fn has_gem(&self) -> bool {
+        false
+    }
+    fn get_gem_type(&self) -> Gem {
+        Gem::None
+    }
+}
+
 pub struct Key {
     pub(crate) has_key: bool
 }
@@ -0,0 +1,114 @@
+use crate::blackboard::*;
+use crate::util::*;
+use crate::entity::*;
+use crate::behavior::*;
+use crate::attack::*;
+use std::time::Instant;
+
+// Turret. Rooted to the spot, fires bullet patterns whenever it can see the player.
+// Alternates between a ring of shots and an aimed spread, and flashes before each volley.
+
+// How close (px) the player has to be before it wakes up
+pub const TURRET_RANGE: f64 = 450.0;
+// Time (ms) between volleys
+pub const TURRET_FIRE_TIME: u128 = 1600;
+// How long (ms) before a volley the turret flashes
+pub const TURRET_TELEGRAPH_TIME: u128 = 300;
+// Ring of shots all the way around, turned half a gap each ring so the gaps move
+pub const TURRET_RING: Emitter = Emitter {
+    count: 8,
+    spread: std::f32::consts::PI * 2.0,
+    rotation: std::f32::consts::PI / 8.0,
+    delay: TURRET_FIRE_TIME,
+    behavior: None,
+};
+// Three shots at the player, one straight and one 15 degrees off either side
+pub const TURRET_SPREAD: Emitter = Emitter {
+    count: 3,
+    spread: std::f32::consts::FRAC_PI_6,
+    rotation: 0.0,
+    delay: TURRET_FIRE_TIME,
+    behavior: None,
+};
+
+// Enemy component for turrets
+#[derive(Copy, Clone, Debug)]
+pub struct TurretState {
+    pub volleys: u32, // Volleys fired so far, even ones are rings and odd ones spreads
+}
+
+impl TurretState {
+    pub fn new() -> TurretState {
+        TurretState { volleys: 0 }
+    }
+}
+
+pub struct TurretBehavior;
+
+impl EnemyBehavior for TurretBehavior {
+    // Looks all the way around, so it uses its own sight check instead of a vision cone
+    fn perceives(&self) -> bool { false }
+
+    fn attack(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        // Lost sight of the player, wait for them to come back
+        if !Enemy::player_in_sight(enemy, blackboard) || Enemy::distance_to_player(enemy, blackboard) > TURRET_RANGE {
+            enemy.telegraph = false;
+            enemy.state = State::Idle;
+            return;
+        }
+
+        let volleys = match enemy.turret {
+            Some( turret ) => turret.volleys,
+            None => 0,
+        };
+        let emitter = if volleys.is_multiple_of(2) { TURRET_RING } else { TURRET_SPREAD };
+        let since_shot = match enemy.last_shot_time {
+            Some( time ) => time.elapsed().as_millis(),
+            None => emitter.delay,
+        };
+        enemy.telegraph = since_shot + TURRET_TELEGRAPH_TIME >= emitter.delay;
+        if !emitter.ready(enemy.last_shot_time) {
+            return;
+        }
+
+        if volleys.is_multiple_of(2) {
+            fire_emitter(enemy, &emitter, Vec2::new(1.0, 0.0), volleys / 2);
+        } else {
+            match aim_at_player(enemy, blackboard) {
+                Some( aim ) => fire_emitter(enemy, &emitter, aim, 0),
+                None => {}
+            }
+        }
+        match enemy.turret {
+            Some( ref mut turret ) => turret.volleys += 1,
+            None => {}
+        }
+        enemy.last_shot_time = Some(Instant::now());
+        enemy.telegraph = false;
+    }
+
+    fn retreat(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.state = State::Idle;
+    }
+
+    fn take_cover(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.state = State::Idle;
+    }
+
+    fn chase(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.state = State::Idle;
+    }
+
+    fn heal(&self, enemy: & mut Enemy, _blackboard: &BlackBoard){
+        enemy.is_healing = false;
+        enemy.state = State::Idle;
+    }
+
+    fn idle(&self, enemy: & mut Enemy, blackboard: &BlackBoard){
+        if Enemy::distance_to_player(enemy, blackboard) < TURRET_RANGE && Enemy::player_in_sight(enemy, blackboard) {
+            // First volley comes after a full wait so walking in isn't an instant hit
+            enemy.last_shot_time = Some(Instant::now());
+            enemy.state = State::Attack;
+        }
+    }
+}
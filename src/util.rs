@@ -33,24 +33,7 @@ pub const DIAGONAL_VEC: f32 = 0.7071067;
 pub const E_INVINCIBILITY_TIME: u64 = 1000;
 pub const HEAL_TIME: u64 = 2000;
 
-//----Health Enemy Stats----
-pub const HEALTH_ENEMY_ATK_DMG: i32 = 1; //The damage of this enemy's projectile attack
-pub const HEALTH_ENEMY_ATK_PROJSPEED: f32 = 2.0; //The speed of this enemy's projectile attack
-pub const HEALTH_ENEMY_ATK_WIDTH: u32 = 30; //The width of this enemy's projectile attack
-pub const HEALTH_ENEMY_ATK_HEIGHT: u32 = 30; //The height of this enemy's projectile attack
-
-//----Speed Enemy Stats----
-pub const SPEED_ENEMY_ATK_DMG: i32 = 1; //The damage of this enemy's projectile attack
-pub const SPEED_ENEMY_ATK_PROJSPEED: f32 = 4.0; //The speed of this enemy's projectile attack
-pub const SPEED_ENEMY_ATK_WIDTH: u32 = 10; //The width of this enemy's projectile attack
-pub const SPEED_ENEMY_ATK_HEIGHT: u32 = 10; //The height of this enemy's projectile attack
-
-
-//----Attack Enemy Stats----
-pub const ATTACK_ENEMY_ATK_DMG: i32 = 2; //The damage of this enemy's projectile attack
-pub const ATTACK_ENEMY_ATK_PROJSPEED: f32 = 3.0; //The speed of this enemy's projectile attack
-pub const ATTACK_ENEMY_ATK_WIDTH: u32 = 20; //The width of this enemy's projectile attack
-pub const ATTACK_ENEMY_ATK_HEIGHT: u32 = 20; //The height of this enemy's projectile attack
+// Per enemy stats live in assets/enemies.txt
 
 
 //-------------------------------Start Position for Procgen---------------------------------
@@ -112,6 +95,19 @@ pub enum SpriteID {
     GemYellow,
     Bomb,
     Explosion,
+    HalfHeart,
+    Heart,
+    Coin,
+}
+
+// Things an enemy can drop besides its gem
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Loot {
+    Nothing,
+    HalfHeart,
+    Heart,
+    Bomb,
+    Coin,
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -4,6 +4,9 @@ use crate::util::*;
 use crate::entity::*;
 use crate::tile::*;
 use crate::blackboard::*;
+use crate::miniboss::*;
+use crate::director;
+use crate::boxes::Layer;
 use crate::player::PowerUp;
 use std::time::Instant;
 
@@ -20,6 +23,10 @@ pub fn base(game: &mut Game, menu: &mut MenuState) {
 
         let current_tile = &mut game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].tiles[game.player.current_frame_tile.y as usize][game.player.current_frame_tile.x as usize];
         let damage_and_adjust = |game: &mut Game, menu: &mut MenuState| {
+            // Only hurts if the player is on a layer hazards can touch
+            if !game.player.box_es.interacts_with(Layer::Hazard) {
+                return;
+            }
             game.player.take_damage(1, P_INVINCIBILITY_TIME);
             game.player.speed_adjust(WalkoverAction::Damage);
             if game.player.death() {
@@ -90,8 +97,8 @@ pub fn base(game: &mut Game, menu: &mut MenuState) {
                     game.trans_dir = Direction::Down;
                 }
 
-                // Adjust number of enemies and type based off of player health
-                game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].ease_enemy_difficulty(game.player.hp);
+                // Adjust number of enemies and how hard they push based off of how the last rooms went
+                director::enter_room(game);
 
                 // Player position has already been set, so we can reposition enemies right here.
                 game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize].reposition_enemies(game.player.pos);
@@ -131,13 +138,23 @@ pub fn base(game: &mut Game, menu: &mut MenuState) {
                 game.player.has_bomb = true;
             }
 
+            // Loot pickups
+            WalkoverAction::Heal(amount) => {
+                game.player.heal(amount);
+            }
+            WalkoverAction::GiveCoin => {
+                game.player.coins += 1;
+            }
+
             WalkoverAction::Damage => {
                 //println!("You've stepped on spikes!");
                 damage_and_adjust(game, menu);
             }
 
             WalkoverAction::GoToNextFloor => {
-                if game.player.has_key {
+                if mini_boss_alive(game.current_room()) {
+                    println!("The guardian is still blocking the trapdoor!");
+                } else if game.player.has_key {
                     println!("Congratulations! You made it to the next floor!!!");
                     game.map.floors[game.cf].rooms[game.cr.y as usize][game.cr.x as usize]
                         .tiles[game.player.current_frame_tile.y as usize][game.player.current_frame_tile.x as usize].unlock();
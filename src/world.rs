@@ -0,0 +1,300 @@
+use crate::util::*;
+use crate::arena::*;
+use crate::boxes::{Box, Layer};
+use crate::collision::move_and_collide;
+use crate::entity::MovementLayer;
+use crate::navigation::tile_of;
+use crate::tile::Tile;
+use sdl2::rect::Rect;
+use std::time::Instant;
+
+// Component based entity store for the simpler things in a room (crates now, and later pickups,
+// hazards, companions). An entity is just a position plus whichever components it has, and the
+// systems below each run over every entity that has the components they care about. A new kind of
+// thing is a new spawn function putting components together, not a new struct with its own copy
+// of movement, health and timers.
+//
+// Only room props live in here for now. The player, enemies and their shots still have their own
+// structs with their own position, health and timer fields, and enemies keep the state only some
+// kinds need in optional components on Enemy instead (see entity.rs).
+
+// Breakable crates: how many hits they take, how big they are and their chance of having loot
+pub const CRATE_HP: i32 = 2;
+pub const CRATE_SIZE: u32 = 48;
+pub const CRATE_LOOT_CHANCE: f32 = 0.5;
+// Time (ms) a crate can't be hit again after a hit, so one swing counts once
+pub const CRATE_INVINCIBILITY_TIME: u128 = 300;
+// Splinters thrown out when a crate breaks: how many, how fast, how quickly they slow down (fraction
+// of speed kept each frame) and how long (ms) they last
+pub const DEBRIS_PIECES: u32 = 6;
+pub const DEBRIS_SPEED: f32 = 4.0;
+pub const DEBRIS_DRIFT: f32 = 0.85;
+pub const DEBRIS_TIME: u128 = 350;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Velocity {
+    pub dir: Vec2<f32>, // Which way it's moving, normalised or zero
+    pub speed: f32, // px per frame at time_scale 1.0
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Hp {
+    pub hp: i32,
+    pub max_hp: i32,
+    pub invincibility: u128, // Time (ms) after a hit before it can be hit again
+    last_hit: Option<Instant>,
+}
+
+impl Hp {
+    pub fn new(hp: i32, invincibility: u128) -> Hp {
+        Hp { hp, max_hp: hp, invincibility, last_hit: None }
+    }
+
+    // Returns true if the hit landed
+    pub fn take_damage(&mut self, amount: i32) -> bool {
+        match self.last_hit {
+            Some( time ) if time.elapsed().as_millis() < self.invincibility => return false,
+            _ => {}
+        }
+        self.hp = (self.hp - amount).max(0);
+        self.last_hit = Some(Instant::now());
+        true
+    }
+
+    pub fn dead(&self) -> bool {
+        self.hp <= 0
+    }
+}
+
+// How an entity decides where to go. Things like a companion following the player go here.
+#[derive(Copy, Clone, Debug)]
+pub enum Ai {
+    Drift(f32), // Keeps going the way it was thrown, keeping this fraction of its speed each frame
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Lifetime {
+    pub spawned: Instant,
+    pub length: u128, // ms
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sprite {
+    Crate,
+    Debris,
+}
+
+#[derive(Clone)]
+pub struct Entity {
+    pub pos: Vec2<f32>,
+    pub velocity: Option<Velocity>,
+    pub collider: Option<Box>,
+    pub solid: bool, // Blocks the player's walkbox, needs a collider
+    pub health: Option<Hp>,
+    pub ai: Option<Ai>,
+    pub sprite: Option<Sprite>,
+    pub lifetime: Option<Lifetime>,
+    pub loot: Option<Loot>, // Left behind when its health runs out
+}
+
+impl Entity {
+    // A bare entity, spawn functions add the components they need
+    pub fn new(pos: Vec2<f32>) -> Entity {
+        Entity {
+            pos,
+            velocity: None,
+            collider: None,
+            solid: false,
+            health: None,
+            ai: None,
+            sprite: None,
+            lifetime: None,
+            loot: None,
+        }
+    }
+
+    pub fn get_hitbox(&self) -> Option<Rect> {
+        self.collider.map(|c| c.get_hitbox(self.pos))
+    }
+
+    pub fn get_walkbox(&self) -> Option<Rect> {
+        self.collider.map(|c| c.get_walkbox(self.pos))
+    }
+}
+
+pub struct World {
+    pub entities: Arena<Entity>,
+}
+
+impl World {
+    pub fn new() -> World {
+        World { entities: Arena::new() }
+    }
+
+    pub fn spawn(&mut self, entity: Entity) -> Handle {
+        self.entities.insert(entity)
+    }
+
+    // A crate standing at pos, maybe with loot in it
+    pub fn spawn_crate(&mut self, pos: Vec2<f32>, loot: Loot) -> Handle {
+        let mut entity = Entity::new(pos);
+        // Hitbox sits on top of the walkbox like everything else's, so the walkbox is the bottom half
+        entity.collider = Some(Box::new(
+            Vec2::new(CRATE_SIZE, CRATE_SIZE / 2),
+            Vec2::new(CRATE_SIZE, CRATE_SIZE / 2),
+            Vec2::new(0, 0)
+        ).with_layer(Layer::Prop));
+        entity.solid = true;
+        entity.health = Some(Hp::new(CRATE_HP, CRATE_INVINCIBILITY_TIME));
+        entity.sprite = Some(Sprite::Crate);
+        if loot != Loot::Nothing {
+            entity.loot = Some(loot);
+        }
+        self.spawn(entity)
+    }
+
+    // Splinters flying out from pos
+    pub fn spawn_debris(&mut self, pos: Vec2<f32>) {
+        for i in 0..DEBRIS_PIECES {
+            let angle = i as f32 / DEBRIS_PIECES as f32 * std::f32::consts::PI * 2.0;
+            let mut entity = Entity::new(pos);
+            entity.velocity = Some(Velocity { dir: Vec2::new(angle.cos(), angle.sin()), speed: DEBRIS_SPEED });
+            entity.ai = Some(Ai::Drift(DEBRIS_DRIFT));
+            entity.sprite = Some(Sprite::Debris);
+            entity.lifetime = Some(Lifetime { spawned: Instant::now(), length: DEBRIS_TIME });
+            self.spawn(entity);
+        }
+    }
+
+    // Tiles something solid is standing on. Walking enemies path around these and shots stop at them.
+    pub fn solid_tiles(&self) -> Vec<Vec2<i32>> {
+        self.entities.iter().filter(|e| e.solid).map(|e| tile_of(e.pos)).collect()
+    }
+
+    // Moves the walkbox from start to end, stopping short of anything solid. Tries sliding along
+    // each axis before giving up, same as tile collision.
+    pub fn block(&self, start: Vec2<f32>, end: Vec2<f32>, size: Vec2<u32>) -> Vec2<f32> {
+        let hits = |pos: Vec2<f32>| {
+            let walkbox = Rect::new(pos.x as i32 - (size.x / 2) as i32, pos.y as i32 - (size.y / 2) as i32, size.x, size.y);
+            self.entities.iter().any(|e| e.solid && match e.get_walkbox() {
+                Some( other ) => other.has_intersection(walkbox),
+                None => false,
+            })
+        };
+        // Already overlapping, e.g. a crate under the door, let it walk out
+        if hits(start) || !hits(end) {
+            return end;
+        }
+        if !hits(Vec2::new(end.x, start.y)) {
+            return Vec2::new(end.x, start.y);
+        }
+        if !hits(Vec2::new(start.x, end.y)) {
+            return Vec2::new(start.x, end.y);
+        }
+        start
+    }
+
+    // Hits everything rect touches that hitter is allowed to affect. Returns how many were hit.
+    pub fn damage_in(&mut self, rect: Rect, hitter: &Box, amount: i32) -> u32 {
+        let mut hit = 0;
+        for entity in self.entities.iter_mut() {
+            let collider = match entity.collider {
+                Some( c ) => c,
+                None => continue,
+            };
+            if !hitter.collides_with(&collider) || !collider.get_hitbox(entity.pos).has_intersection(rect) {
+                continue;
+            }
+            let landed = match entity.health.as_mut() {
+                Some( health ) => health.take_damage(amount),
+                None => false,
+            };
+            if landed {
+                hit += 1;
+            }
+        }
+        hit
+    }
+}
+
+// Runs every system for one frame. Returns where anything that broke was and what it dropped.
+pub fn update(world: &mut World, tiles: &Vec<Vec<std::boxed::Box<dyn Tile>>>, time_scale: f32) -> Vec<(Vec2<f32>, Loot)> {
+    ai_system(world, time_scale);
+    movement_system(world, tiles, time_scale);
+    lifetime_system(world);
+    health_system(world)
+}
+
+// Entities with an Ai and a Velocity decide where to go
+fn ai_system(world: &mut World, time_scale: f32) {
+    for entity in world.entities.iter_mut() {
+        let (ai, velocity) = match (entity.ai, entity.velocity.as_mut()) {
+            (Some( ai ), Some( velocity )) => (ai, velocity),
+            _ => continue,
+        };
+        match ai {
+            Ai::Drift(keep) => {
+                velocity.speed *= keep.powf(time_scale);
+            }
+        }
+    }
+}
+
+// Entities with a Velocity move, walking around tiles if they also have a collider
+fn movement_system(world: &mut World, tiles: &Vec<Vec<std::boxed::Box<dyn Tile>>>, time_scale: f32) {
+    for entity in world.entities.iter_mut() {
+        let velocity = match entity.velocity {
+            Some( v ) => v,
+            None => continue,
+        };
+        let end = Vec2::new(
+            entity.pos.x + velocity.dir.x * velocity.speed * time_scale,
+            entity.pos.y + velocity.dir.y * velocity.speed * time_scale
+        );
+        entity.pos = match entity.collider {
+            Some( c ) => move_and_collide(tiles, entity.pos, end, c.walkbox, &|w| MovementLayer::Walker.blocked_by(w)),
+            None => end,
+        };
+    }
+}
+
+// Entities with a Lifetime go away once it runs out
+fn lifetime_system(world: &mut World) {
+    let expired: Vec<Handle> = world.entities.iter_with_handles()
+        .filter(|(_, e)| match e.lifetime {
+            Some( l ) => l.spawned.elapsed().as_millis() >= l.length,
+            None => false,
+        })
+        .map(|(handle, _)| handle)
+        .collect();
+    for handle in expired {
+        world.entities.remove(handle);
+    }
+}
+
+// Entities whose Hp has run out are removed, leaving their loot behind
+fn health_system(world: &mut World) -> Vec<(Vec2<f32>, Loot)> {
+    let dead: Vec<Handle> = world.entities.iter_with_handles()
+        .filter(|(_, e)| match e.health {
+            Some( h ) => h.dead(),
+            None => false,
+        })
+        .map(|(handle, _)| handle)
+        .collect();
+    let mut drops = Vec::new();
+    for handle in dead {
+        match world.entities.remove(handle) {
+            Some( entity ) => {
+                if entity.sprite == Some(Sprite::Crate) {
+                    world.spawn_debris(entity.pos);
+                }
+                match entity.loot {
+                    Some( loot ) => drops.push((entity.pos, loot)),
+                    None => {}
+                }
+            }
+            None => {}
+        }
+    }
+    drops
+}